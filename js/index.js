@@ -61,14 +61,22 @@ const FITBOUNDS_PADDING_PIXEL = 0;
       if (!!app || !!actual_coords) return;
       console.log('[index] Instantiating \'App\'');
 
-      app = new wasm.App(el.canvas);
+      app = new wasm.App(el.canvas, 'fixture');
 
       // Ask the WASM app to fetch arrival/departure
       // information. Once the data is fetched,
       // we want to once again update the map for all
       // the coordinates to fit in the map.
 
-      const response = await app.prepare(airports);
+      // When failed, WASM app rejects with an `Error`
+      // which has `kind` (e.g. "http_status") and details.
+      let response;
+      try {
+        response = await app.prepare(airports);
+      } catch (err) {
+        console.error(`[index] (${err.kind}) ${err.message}`, err);
+        return;
+      }
       actual_coords = JSON.parse(response);
 
       bounds = get_bounds(actual_coords);
@@ -78,6 +86,10 @@ const FITBOUNDS_PADDING_PIXEL = 0;
       map.setCenter(center);
 
       app.start(); // Start animation loop
+
+      // Flights are colored by delays.
+      console.log('[index] Delay legend:', app.delay_legend());
+      console.log('[index] Stats:', app.stats());
     });
 
     // Listen to "bounds_changed". Whenever the bounds
@@ -89,6 +101,21 @@ const FITBOUNDS_PADDING_PIXEL = 0;
         app.update(map.getBounds().toJSON());
       }
     });
+
+    // Clicks go to Google map (the canvas is on top,
+    // but lets them through), so we ask the WASM app
+    // what is drawn at the clicked position.
+    map.addListener('click', (e) => {
+      if (!app || !actual_coords || !e.domEvent) return;
+      const rect = el.canvas.getBoundingClientRect();
+      const x = e.domEvent.clientX - rect.left;
+      const y = e.domEvent.clientY - rect.top;
+      const picked = app.pick(x, y);
+      if (picked) {
+        console.log(`[index] Picked ${picked.kind}: ${picked.label}`, picked);
+      }
+      app.select(picked && picked.kind === 'flight' ? picked.id : null);
+    });
   } catch (err) {
     console.error(err);
   }
@@ -25,6 +25,7 @@
 /// to behave just like any other member functions.
 /// It is just that `App` holding onto the context.
 
+use std::rc::Rc;
 use std::sync::Arc;
 use js_sys::Promise;
 use wasm_bindgen::JsValue;
@@ -32,6 +33,19 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::{spawn_local, future_to_promise};
 use web_sys::HtmlCanvasElement;
 
+use crate::aviation::reference::{
+    AirportDatabase,
+    active_airport_database,
+    set_active_airport_database,
+};
+use crate::aviation::search::search_airports;
+use crate::aviation::source::FlightDataSourceKind;
+use crate::constants::{
+    AIRPORT_SEARCH_LIMIT,
+    PICK_TOLERANCE,
+};
+use crate::dimension::geo::{GeoCoord, LatLngBounds};
+use crate::error::{decode_js_value, encode_js_value};
 use crate::proxy::Proxy;
 
 #[wasm_bindgen]
@@ -49,11 +63,25 @@ pub struct App {
 
 #[wasm_bindgen]
 impl App {
+    /// `source` tells where flights come from:
+    /// `"fixture"` (bundled JSON, the default)
+    /// or `"aeroapi"` (FlightAware API).
     #[wasm_bindgen(constructor)]
-    pub fn new(element: HtmlCanvasElement) -> Result<App, JsValue> {
+    pub fn new(
+        element: HtmlCanvasElement,
+        source: Option<String>,
+    ) -> Result<App, JsValue> {
+        let kind: FlightDataSourceKind = match source {
+            Some(name) => FlightDataSourceKind::from_name(&name)
+                .ok_or_else(|| JsValue::from(
+                    format!("Unknown flight data source: {}", name)
+                ))?,
+            None => FlightDataSourceKind::default(),
+        };
+
         Ok(App {
             proxy: Arc::new(
-                Proxy::new(element)
+                Proxy::new(element, Rc::from(kind.create()))
             ),
         })
     }
@@ -79,6 +107,21 @@ impl App {
     /// we are just cloning `std::sync::Arc`,
     /// and does not mean that we are cloning
     /// whatever inside.
+    ///
+    /// Each airport in `airports` may be either a full object
+    /// (`icao`, `iata`, `name`, `city`, `country` and `coord`),
+    /// just an ICAO/IATA code (e.g. `['WSSS', 'HKG']`),
+    /// or a region which expands into airports
+    /// (e.g. `{ country: 'PH' }`, see `RegionSelector`).
+    ///
+    /// When failed, the _Promise_ rejects with a JS `Error`
+    /// having `kind` (e.g. `"http_status"`) and the details
+    /// (see `FlightPackError`). For codes we do not know
+    /// (or regions without airports), `kind` is
+    /// `"unresolved_airports"` with `codes`. Regions with
+    /// `types` or `limit` need `load_airport_database` first
+    /// (otherwise, `kind` is `"decode"` with `field`,
+    /// e.g. `"[1].limit"`).
     #[wasm_bindgen]
     pub fn prepare(&mut self, airports: &JsValue) -> Promise {
         let this = self.proxy.clone();
@@ -86,7 +129,8 @@ impl App {
 
         future_to_promise(async move {
             match check_dotenv() {
-                Ok(_) => Proxy::prepare(this, airports).await,
+                Ok(_) => Proxy::prepare(this, airports).await
+                    .map_err(JsValue::from),
                 Err(_) => {
                     Err(JsValue::from("Faild to read dotenv".to_string()))
                 }
@@ -94,6 +138,16 @@ impl App {
         })
     }
 
+    /// How many pages of arrivals/departures to follow
+    /// for each airport (`AERO_API_MAX_PAGES` by default,
+    /// and at least 1). Call it before `prepare`.
+    /// When an airport has more pages than this,
+    /// a warning is logged to the console.
+    #[wasm_bindgen]
+    pub fn set_max_pages(&mut self, max_pages: usize) -> Result<(), JsValue> {
+        Proxy::set_max_pages(self.proxy.clone(), max_pages)
+    }
+
     /// JS runs `App::start()` to start
     /// the animation loop. By using
     /// `wasm_bindgen_futures::spawn_local`,
@@ -131,6 +185,295 @@ impl App {
             Proxy::set_bounds(this, bounds).await;
         })
     }
+
+    /// Makes the app time go `scale` times faster
+    /// than the real time (e.g. `60` for 1 min/sec).
+    /// Throws unless it is above 0 and up to
+    /// `CLOCK_MAX_TIME_SCALE` (use `pause` to stop).
+    #[wasm_bindgen]
+    pub fn set_time_scale(&mut self, scale: f64) -> Result<(), JsValue> {
+        Proxy::set_time_scale(self.proxy.clone(), scale)
+    }
+
+    #[wasm_bindgen]
+    pub fn pause(&mut self) {
+        Proxy::pause(self.proxy.clone());
+    }
+
+    #[wasm_bindgen]
+    pub fn resume(&mut self) {
+        Proxy::resume(self.proxy.clone());
+    }
+
+    /// Jumps the app time to `iso_timestamp`
+    /// (e.g. `"2022-08-24T09:00:00Z"`).
+    #[wasm_bindgen]
+    pub fn seek(&mut self, iso_timestamp: String) -> Result<(), JsValue> {
+        Proxy::seek(self.proxy.clone(), iso_timestamp)
+    }
+
+    /// Draws flights along `"great_circle"` (default)
+    /// or `"rhumb"` lines. When `flight_id` (`fa_flight_id`)
+    /// is given, only that flight is switched.
+    #[wasm_bindgen]
+    pub fn set_flight_path(
+        &mut self,
+        kind: String,
+        flight_id: Option<String>,
+    ) -> Result<(), JsValue> {
+        Proxy::set_path_kind(self.proxy.clone(), kind, flight_id)
+    }
+
+    /// Tells what is drawn at (`x`, `y`) (in CSS pixels
+    /// relative to the canvas, e.g. `offsetX` and `offsetY`).
+    /// Returns an object with `kind` (`"flight"` or `"airport"`),
+    /// `id`, `label`, `distance` and so on (see `PickResult`),
+    /// or `null` when nothing is within `tolerance`
+    /// (defaults to `PICK_TOLERANCE`).
+    #[wasm_bindgen]
+    pub fn pick(
+        &self,
+        x: f64,
+        y: f64,
+        tolerance: Option<f64>,
+    ) -> Result<JsValue, JsValue> {
+        let picked = Proxy::pick(
+            self.proxy.clone(),
+            x,
+            y,
+            tolerance.unwrap_or(PICK_TOLERANCE),
+        )?;
+
+        encode_js_value(&picked).map_err(JsValue::from)
+    }
+
+    /// Turns (`x`, `y`) (in CSS pixels relative to the canvas,
+    /// same as `pick`) into `{ lat, lng }` on the map.
+    #[wasm_bindgen]
+    pub fn unproject(&self, x: f64, y: f64) -> Result<JsValue, JsValue> {
+        let coord = Proxy::unproject(self.proxy.clone(), x, y)?;
+
+        encode_js_value(&coord).map_err(JsValue::from)
+    }
+
+    /// Draws with `"web_mercator"` (default, as Google Maps)
+    /// or `"equirectangular"` (also `"mercator"` for the former).
+    #[wasm_bindgen]
+    pub fn set_projection(&mut self, name: String) -> Result<(), JsValue> {
+        Proxy::set_projection(self.proxy.clone(), name)
+    }
+
+    /// Shows a tooltip for `flight_id` (`fa_flight_id`,
+    /// e.g. `id` of what `App::pick` returned).
+    /// Give `null` to hide it. The tooltip is not shown
+    /// while the flight is filtered out.
+    #[wasm_bindgen]
+    pub fn select(&mut self, flight_id: Option<String>) -> Result<(), JsValue> {
+        Proxy::select(self.proxy.clone(), flight_id)
+    }
+
+    /// Flights are colored by their delays. `thresholds`
+    /// (in minutes) may have any of `early`, `minor`
+    /// and `major` (e.g. `{ "minor": 10, "major": 45 }`).
+    /// Throws (`kind: "decode"`) when any is negative
+    /// or over a day, or when `minor` exceeds `major`.
+    #[wasm_bindgen]
+    pub fn set_delay_thresholds(&mut self, thresholds: &JsValue) -> Result<(), JsValue> {
+        Proxy::set_delay_thresholds(self.proxy.clone(), thresholds.clone())
+    }
+
+    /// Flight lines are colored by `"delay"` (default)
+    /// or by `"airline"` (brand colors).
+    #[wasm_bindgen]
+    pub fn set_flight_color(&mut self, color_by: String) -> Result<(), JsValue> {
+        Proxy::set_color_by(self.proxy.clone(), color_by)
+    }
+
+    /// Cancelled and diverted flights are shown by default
+    /// (cancelled ones in dashed lines). `options` may have
+    /// any of `include_cancelled`, `include_diverted`
+    /// and `dash_cancelled` (all booleans).
+    #[wasm_bindgen]
+    pub fn set_status_options(&mut self, options: &JsValue) -> Result<(), JsValue> {
+        Proxy::set_status_options(self.proxy.clone(), options.clone())
+    }
+
+    /// Shows only the flights meeting `filter`, which may have
+    /// any of `idents` (including codeshares, e.g. `['UA9416']`),
+    /// `operators`, `aircraft_types`, `wake`, `statuses`,
+    /// `min_route_distance`, `max_route_distance` (statute miles),
+    /// `origins`, `destinations`, `min_delay`, `max_delay`
+    /// (minutes, never met when delays are unknown),
+    /// `delay_buckets` and `expression`. For instance:
+    ///
+    /// ```js
+    /// app.set_filter({ operators: ['CX'], wake: ['heavy', 'super'] });
+    /// app.set_filter({
+    ///   expression: 'operator == "CPA" && dest.country == "Singapore"',
+    /// });
+    /// ```
+    ///
+    /// Throws when `filter` has unknown fields, or when
+    /// `expression` is invalid (with `kind: "expression"`,
+    /// `message` and `position` telling where).
+    #[wasm_bindgen]
+    pub fn set_filter(&mut self, filter: &JsValue) -> Result<(), JsValue> {
+        Proxy::set_filter(self.proxy.clone(), filter.clone())
+    }
+
+    /// Shows all the flights again.
+    #[wasm_bindgen]
+    pub fn clear_filter(&mut self) -> Result<(), JsValue> {
+        Proxy::clear_filter(self.proxy.clone())
+    }
+
+    /// A list of delay buckets, each having `bucket`, `label`,
+    /// `color`, `min_minutes` and `max_minutes`
+    /// (`null` when unbounded).
+    #[wasm_bindgen]
+    pub fn delay_legend(&self) -> Result<JsValue, JsValue> {
+        let legend = Proxy::delay_legend(self.proxy.clone())?;
+
+        encode_js_value(&legend).map_err(JsValue::from)
+    }
+
+    /// Summary numbers for the flights (refreshed whenever
+    /// `prepare` finishes): `flights`, `total_route_distance`
+    /// (statute miles), `airports` (each with `arrivals`,
+    /// `departures`, `average_delay` and `max_delay` in minutes),
+    /// the busiest `routes` and `operators`.
+    #[wasm_bindgen]
+    pub fn stats(&self) -> Result<JsValue, JsValue> {
+        let stats = Proxy::stats(self.proxy.clone())?;
+
+        encode_js_value(&stats).map_err(JsValue::from)
+    }
+
+    /// Searches our Airport Database by ICAO/IATA codes,
+    /// names, cities and countries (tolerating typos).
+    /// Returns at most `limit` (10 by default) airports,
+    /// the best match first, each having the fields
+    /// of the airport, `score` and `matched`
+    /// (`"icao"`, `"iata"`, `"name"`, `"city"` or `"country"`).
+    ///
+    /// ```js
+    /// app.search_airports('Changi'); // [{ icao: 'WSSS', ... }]
+    /// ```
+    #[wasm_bindgen]
+    pub fn search_airports(
+        &self,
+        query: String,
+        limit: Option<usize>,
+    ) -> Result<JsValue, JsValue> {
+        let results = search_airports(
+            active_airport_database().airports(),
+            &query,
+            limit.unwrap_or(AIRPORT_SEARCH_LIMIT),
+        );
+
+        encode_js_value(&results).map_err(JsValue::from)
+    }
+
+    /// Uses OurAirports `airports.csv` (given as a string)
+    /// for our Airport Database from now on. When `merge`
+    /// is `true` (the default), airports in `csv` are added
+    /// to (and override) the bundled ones. Returns how many
+    /// airports the database now has.
+    ///
+    /// This is the only way to have `elevation`, `type`
+    /// and `iso_region` of airports (the bundled ones have
+    /// `iso_country` and most of `tz` only).
+    ///
+    /// Throws when `csv` is malformed (`kind: "decode"`
+    /// with `field` telling the row, e.g. `"row 12 (latitude_deg)"`).
+    /// Call it before `prepare` for it to take effect.
+    #[wasm_bindgen]
+    pub fn load_airport_database(
+        &self,
+        csv: String,
+        merge: Option<bool>,
+    ) -> Result<usize, JsValue> {
+        let loaded = AirportDatabase::from_ourairports_csv(&csv)?;
+
+        let db = if merge.unwrap_or(true) {
+            AirportDatabase::bundled().merge(loaded)
+        } else {
+            loaded
+        };
+
+        let len: usize = db.len();
+        set_active_airport_database(db);
+        Ok(len)
+    }
+
+    /// Goes back to the bundled Airport Database.
+    #[wasm_bindgen]
+    pub fn reset_airport_database(&self) {
+        set_active_airport_database(AirportDatabase::bundled());
+    }
+
+    /// `k` (10 by default) airports nearest from
+    /// the coordinate, the nearest first. Each has
+    /// the fields of the airport and `distance` (km).
+    #[wasm_bindgen]
+    pub fn nearest_airports(
+        &self,
+        lat: f64,
+        lng: f64,
+        k: Option<usize>,
+    ) -> Result<JsValue, JsValue> {
+        let found = active_airport_database().nearest(
+            &GeoCoord::new(lat, lng),
+            k.unwrap_or(AIRPORT_SEARCH_LIMIT),
+        );
+
+        encode_js_value(&found).map_err(JsValue::from)
+    }
+
+    /// Airports within `km` from the coordinate,
+    /// the nearest first (same as `nearest_airports`).
+    #[wasm_bindgen]
+    pub fn airports_within_radius(
+        &self,
+        lat: f64,
+        lng: f64,
+        km: f64,
+    ) -> Result<JsValue, JsValue> {
+        let found = active_airport_database().within_radius(
+            &GeoCoord::new(lat, lng),
+            km,
+        );
+
+        encode_js_value(&found).map_err(JsValue::from)
+    }
+
+    /// Airports inside `bounds` (`north`, `east`, `south`
+    /// and `west`, as `google.maps.LatLngBounds.toJSON()`
+    /// gives), or inside what the map currently shows
+    /// when `bounds` is not given. For instance,
+    /// to plot airports the map is showing:
+    ///
+    /// ```js
+    /// const airports = app.airports_within_bounds()
+    ///   .filter(a => a.iata !== 'N/A')
+    ///   .map(a => a.icao);
+    /// ```
+    #[wasm_bindgen]
+    pub fn airports_within_bounds(
+        &self,
+        bounds: Option<JsValue>,
+    ) -> Result<JsValue, JsValue> {
+        let bounds: LatLngBounds = match bounds {
+            Some(bounds) if !bounds.is_undefined() && !bounds.is_null() => {
+                decode_js_value::<LatLngBounds>(&bounds, "bounds")?
+            },
+            _ => Proxy::bounds(self.proxy.clone()),
+        };
+
+        let found = active_airport_database().within_bounds(&bounds);
+
+        encode_js_value(&found).map_err(JsValue::from)
+    }
 }
 
 fn check_dotenv() -> Result<(), String> {
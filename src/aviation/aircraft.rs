@@ -0,0 +1,82 @@
+/// FlightAware API tells `aircraft_type` (usually ICAO
+/// designators such as `B77W`). The file provides
+/// a lookup table for aircraft types so that we know
+/// how big they are (for markers), and how fast
+/// they fly (for estimating positions).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::get_json;
+
+/// ICAO wake turbulence categories.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeCategory {
+    Light, // MTOW 7t or less
+    #[default]
+    Medium,
+    Heavy, // MTOW 136t or more
+    Super, // A380
+}
+
+impl WakeCategory {
+    /// Multiplied to `FLIGHT_MARKER_SIZE`.
+    pub fn marker_scale(&self) -> f64 {
+        match self {
+            WakeCategory::Light => 0.7,
+            WakeCategory::Medium => 1.0,
+            WakeCategory::Heavy => 1.3,
+            WakeCategory::Super => 1.6,
+        }
+    }
+}
+
+/// Aircraft type information stored in our Aircraft Type Database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AircraftType {
+    pub icao: String, // B77W
+    pub manufacturer: String, // Boeing
+    pub model: String, // 777-300ER
+    pub wake: WakeCategory,
+    pub cruise_speed: f64, // Typical cruise speed (km/h)
+    pub range: f64, // Typical range (km)
+}
+
+lazy_static! {
+    /// Aircraft type lookup table (keyed by ICAO designator).
+    #[derive(Debug)]
+    pub static ref AIRCRAFT_TYPE_REFERENCE: HashMap<String, AircraftType> = {
+        let json = &include_str!("../../json/aircraft_types.json");
+        let data = get_json::<Vec<AircraftType>>(json);
+
+        let mut hashmap = HashMap::new();
+        data.iter().for_each(|item| {
+            hashmap.insert(
+                item.icao.clone(),
+                item.clone(),
+            );
+        });
+
+        hashmap
+    };
+}
+
+/// See if the specified aircraft type is in the table.
+pub fn lookup_aircraft_type(icao: &str) -> Option<AircraftType> {
+    AIRCRAFT_TYPE_REFERENCE.get(&icao.to_uppercase()).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_aircraft_type_by_designator() {
+        let b77w = lookup_aircraft_type("B77W").unwrap();
+        assert_eq!(b77w.model, "777-300ER");
+        assert_eq!(b77w.wake, WakeCategory::Heavy);
+        assert_eq!(lookup_aircraft_type("a388").unwrap().wake, WakeCategory::Super);
+        assert!(lookup_aircraft_type("ZZZZ").is_none());
+    }
+}
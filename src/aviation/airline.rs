@@ -0,0 +1,123 @@
+/// `operator` of FlightAware API is just a code (usually
+/// ICAO, e.g. `SIA`). The file provides a lookup table
+/// for airlines (by either ICAO or IATA code) so that
+/// we know their names and brand colors, and so that
+/// we can convert flight idents between ICAO and IATA
+/// (e.g. `SIA12` and `SQ12`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::get_json;
+
+/// Airline information stored in our Airline Database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Airline {
+    pub icao: String, // SIA
+    pub iata: String, // SQ
+    pub name: String, // Singapore Airlines
+    pub callsign: String, // SINGAPORE
+    pub country: String, // Singapore
+    pub color: String, // Brand color (e.g. `#f2a900`)
+}
+
+lazy_static! {
+    /// Airline lookup table (keyed by ICAO code).
+    #[derive(Debug)]
+    pub static ref AIRLINE_REFERENCE: HashMap<String, Airline> = {
+        let json = &include_str!("../../json/airlines.json");
+        let data = get_json::<Vec<Airline>>(json);
+
+        let mut hashmap = HashMap::new();
+        data.iter().for_each(|item| {
+            hashmap.insert(
+                item.icao.clone(),
+                item.clone(),
+            );
+        });
+
+        hashmap
+    };
+
+    /// IATA code to ICAO code.
+    #[derive(Debug)]
+    pub static ref AIRLINE_IATA_INDEX: HashMap<String, String> = {
+        let mut hashmap = HashMap::new();
+        AIRLINE_REFERENCE.values().for_each(|item| {
+            hashmap.insert(
+                item.iata.clone(),
+                item.icao.clone(),
+            );
+        });
+
+        hashmap
+    };
+}
+
+/// See if the specified airline is in the table.
+/// `code` may be either ICAO (3 letters) or IATA (2 characters).
+pub fn lookup_airline(code: &str) -> Option<Airline> {
+    let code: String = code.to_uppercase();
+
+    match code.len() {
+        3 => AIRLINE_REFERENCE.get(&code).cloned(),
+        2 => AIRLINE_IATA_INDEX
+            .get(&code)
+            .and_then(|icao| AIRLINE_REFERENCE.get(icao))
+            .cloned(),
+        _ => None,
+    }
+}
+
+/// `SIA12` ---> `SQ12`
+pub fn icao_ident_to_iata(ident: &str) -> Option<String> {
+    let (code, number) = split_ident(ident, 3)?;
+    lookup_airline(code).map(|airline| format!("{}{}", airline.iata, number))
+}
+
+/// `SQ12` ---> `SIA12`
+pub fn iata_ident_to_icao(ident: &str) -> Option<String> {
+    let (code, number) = split_ident(ident, 2)?;
+    lookup_airline(code).map(|airline| format!("{}{}", airline.icao, number))
+}
+
+// Airline code of `len` characters followed by the flight
+// number (digits with an optional letter at the end).
+fn split_ident(ident: &str, len: usize) -> Option<(&str, &str)> {
+    if !ident.is_ascii() || ident.len() <= len {
+        return None;
+    }
+
+    let (code, number) = ident.split_at(len);
+
+    if !number.starts_with(|c: char| c.is_ascii_digit()) ||
+        !number.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return None;
+    }
+
+    Some((code, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_airline_by_icao_or_iata() {
+        assert_eq!(lookup_airline("SIA").unwrap().name, "Singapore Airlines");
+        assert_eq!(lookup_airline("sq").unwrap().icao, "SIA");
+        assert_eq!(lookup_airline("5J").unwrap().icao, "CEB");
+        assert!(lookup_airline("ZZZ").is_none());
+    }
+
+    #[test]
+    fn converts_idents_between_icao_and_iata() {
+        assert_eq!(icao_ident_to_iata("SIA12"), Some("SQ12".into()));
+        assert_eq!(iata_ident_to_icao("SQ12"), Some("SIA12".into()));
+        assert_eq!(iata_ident_to_icao("5J241"), Some("CEB241".into()));
+        assert_eq!(icao_ident_to_iata("SIA"), None);
+        assert_eq!(icao_ident_to_iata("ZZZ12"), None);
+        assert_eq!(iata_ident_to_icao("SQ-12"), None);
+    }
+}
@@ -1,16 +1,31 @@
 /// JS calls `app.prepare(airports)` where `airports`
 /// is a list of airports to be plotted on Google Map.
+/// Each may be either a full `TargetAirportRawData`,
+/// a bare ICAO/IATA code (e.g. `"WSSS"` or `"SIN"`)
+/// which we resolve using our Airport Database,
+/// or a region (`RegionSelector`) which expands
+/// into airports in our Airport Database.
 /// This file provides associated structs.
 
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
+use std::sync::Arc;
 use wasm_bindgen::prelude::JsValue;
 use web_sys::{
     // console,
     CanvasRenderingContext2d,
-    TextMetrics,
 };
 
+use crate::aviation::reference::{
+    AirportDatabase,
+    AirportDetails,
+    AirportKind,
+    AirportRefer,
+    active_airport_database,
+    has_iata,
+    lookup_airport,
+    lookup_airport_database,
+};
 use crate::constants::{
     FONT_SIZE,
     FONT_COLOR,
@@ -20,16 +35,16 @@ use crate::constants::{
     AIRPORT_TEXT_WIDTH_DEFAULT,
     AIRPORT_TEXT_HEIGHT_DEFAULT,
 };
-use crate::dimension::canvas::Canvas;
 use crate::dimension::geo::{
     GeoCoordTrait,
     GeoCoord,
     LatLngBounds,
+    Projection,
 };
 use crate::dimension::point::PointCoord;
-use crate::dimension::geo::get_mercator_position;
 use crate::dimension::{Size, get_window_size};
-use crate::utils::lazy_round;
+use crate::error::FlightPackError;
+use crate::utils::{lazy_round, measure_text};
 
 const AIRPORT_DOT_START: f64 = 0.0;
 const AIRPORT_DOT_END: f64 = PI * 2.0;
@@ -38,7 +53,10 @@ const AIRPORT_DOT_END: f64 = PI * 2.0;
 /// is given from JS, this is how it looks.
 /// For them, we will later fetch arrival/departure
 /// information from FlightAware API.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Optional `tz`, `elevation`, `type`, `iso_country`,
+/// `iso_region` and `scheduled_service` are filled from our Airport Database
+/// when not given.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TargetAirportRawData {
     pub icao: String, // VHHH
     pub iata: String, // HKG
@@ -46,6 +64,255 @@ pub struct TargetAirportRawData {
     pub city: String, // Hong Kong
     pub country: String, // Hong Kong
     pub coord: GeoCoord,
+    #[serde(flatten)]
+    pub details: AirportDetails,
+}
+
+impl From<AirportRefer> for TargetAirportRawData {
+    fn from(refer: AirportRefer) -> Self {
+        TargetAirportRawData {
+            icao: refer.icao,
+            iata: refer.iata,
+            name: refer.name,
+            city: refer.city,
+            country: refer.country,
+            coord: refer.coord,
+            details: refer.details,
+        }
+    }
+}
+
+/// What JS may give for each airport in `airports`
+/// (see `decode_target_airports`).
+#[derive(Debug, Clone)]
+pub enum TargetAirportSpec {
+    Code(String), // WSSS or SIN
+    Full(TargetAirportRawData),
+    Region(RegionSelector),
+}
+
+impl TargetAirportSpec {
+    /// A text is a code, an object with `icao` is a full
+    /// airport, and anything else must be a region.
+    /// Telling them apart first (rather than trying each)
+    /// keeps the path to the offending field when failed.
+    pub fn from_value(
+        value: serde_json::Value,
+    ) -> Result<Self, serde_path_to_error::Error<serde_json::Error>> {
+        match value {
+            serde_json::Value::String(code) => Ok(TargetAirportSpec::Code(code)),
+            serde_json::Value::Object(ref map) if map.contains_key("icao") => {
+                serde_path_to_error::deserialize(value).map(TargetAirportSpec::Full)
+            },
+            _ => serde_path_to_error::deserialize(value).map(TargetAirportSpec::Region),
+        }
+    }
+}
+
+/// Turns what JS gave for `airports` into `TargetAirportSpec`s.
+/// When failed, `field` tells which airport and which field
+/// (e.g. `[2].coord.lat`). `source` is for the error.
+pub fn decode_target_airports(
+    values: Vec<serde_json::Value>,
+    source: &str,
+) -> Result<Vec<TargetAirportSpec>, FlightPackError> {
+    values.into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            TargetAirportSpec::from_value(value).map_err(|err| {
+                let path: String = err.path().to_string();
+                FlightPackError::Decode {
+                    source: source.into(),
+                    field: if path == "." {
+                        format!("[{}]", i)
+                    } else {
+                        format!("[{}].{}", i, path)
+                    },
+                    message: err.inner().to_string(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Selects airports in a region, for instance:
+///
+/// ```js
+/// app.prepare([
+///   { country: 'Singapore' },
+///   { country: 'PH', types: ['large_airport'] },
+///   { bounds: { north: 26, east: 122, south: 21, west: 112 }, limit: 3 },
+/// ]);
+/// ```
+///
+/// `country` is either the name (as in our Airport Database)
+/// or ISO 3166-1 code. When more than one is given, airports
+/// must meet all of them. `types` and `limit` need
+/// an Airport Database knowing types
+/// (see `App::load_airport_database`),
+/// and are rejected with the bundled one.
+///
+/// We have no traffic figures, so `limit` takes the first
+/// ones by type (large airports first), then those with
+/// scheduled service. Ties go to those having IATA codes,
+/// and then by ICAO codes.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegionSelector {
+    pub country: Option<String>,
+    pub bounds: Option<LatLngBounds>,
+    pub types: Option<Vec<AirportKind>>,
+    pub limit: Option<usize>,
+}
+
+impl RegionSelector {
+    /// For errors (e.g. `country:Atlantis`).
+    pub fn label(&self) -> String {
+        let mut parts: Vec<String> = vec![];
+
+        if let Some(country) = &self.country {
+            parts.push(format!("country:{}", country));
+        }
+        if let Some(b) = &self.bounds {
+            parts.push(format!("bounds:{},{},{},{}", b.north, b.east, b.south, b.west));
+        }
+        if let Some(types) = &self.types {
+            let names: Vec<String> = types.iter()
+                .filter_map(|t| serde_json::to_value(t).ok())
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+            parts.push(format!("types:{}", names.join("|")));
+        }
+
+        if parts.is_empty() {
+            "region".into()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    pub fn matches(&self, airport: &AirportRefer) -> bool {
+        self.country.as_ref().is_none_or(|country| {
+            airport.country.eq_ignore_ascii_case(country) ||
+                airport.details.iso_country
+                    .as_ref()
+                    .is_some_and(|iso| iso.eq_ignore_ascii_case(country))
+        }) &&
+            self.bounds.as_ref().is_none_or(|b| b.contains(&airport.coord)) &&
+            self.types.as_ref().is_none_or(|types| {
+                airport.details.kind.is_some_and(|kind| types.contains(&kind))
+            })
+    }
+
+    /// Airports in `db` meeting the selector,
+    /// in the order told above.
+    pub fn expand(&self, db: &AirportDatabase) -> Vec<AirportRefer> {
+        // Only a region with some criteria selects airports.
+        if self.country.is_none() && self.bounds.is_none() && self.types.is_none() {
+            return vec![];
+        }
+
+        let mut airports: Vec<AirportRefer> = match &self.bounds {
+            Some(bounds) => db.within_bounds(bounds),
+            None => db.airports().cloned().collect(),
+        };
+        airports.retain(|a| self.matches(a));
+
+        airports.sort_by_cached_key(|a| (
+            kind_rank(a.details.kind),
+            a.details.scheduled_service != Some(true),
+            !has_iata(a),
+            a.icao.clone(),
+        ));
+
+        if let Some(limit) = self.limit {
+            airports.truncate(limit);
+        }
+
+        airports
+    }
+}
+
+fn kind_rank(kind: Option<AirportKind>) -> u8 {
+    match kind {
+        Some(AirportKind::LargeAirport) => 0,
+        Some(AirportKind::MediumAirport) => 1,
+        Some(AirportKind::SmallAirport) => 2,
+        None => 3,
+        Some(_) => 4,
+    }
+}
+
+/// Turns codes (and regions) into `TargetAirportRawData`.
+/// When any of the codes are not in our Airport Database
+/// (or regions have no airports), returns all of them
+/// at once (so that JS can fix them at once).
+/// The same airport given twice (e.g. `"SIN"` and `"WSSS"`)
+/// appears only once.
+/// Regions with `types` or `limit` fail right away when
+/// the active database knows no types (see `RegionSelector`).
+pub fn resolve_target_airports(
+    specs: Vec<TargetAirportSpec>,
+) -> Result<Vec<TargetAirportRawData>, FlightPackError> {
+    let mut airports: Vec<TargetAirportRawData> = vec![];
+    let mut unresolved: Vec<String> = vec![];
+
+    let db: Arc<AirportDatabase> = active_airport_database();
+
+    for (i, spec) in specs.into_iter().enumerate() {
+        let resolved: Vec<TargetAirportRawData> = match spec {
+            TargetAirportSpec::Full(mut raw) => {
+                if let Some(refer) = lookup_airport_database(&raw.icao) {
+                    raw.details.fill_missing_from(&refer.details);
+                }
+                vec![raw]
+            },
+            TargetAirportSpec::Code(code) => match lookup_airport(&code) {
+                Some(refer) => vec![refer.into()],
+                None => {
+                    unresolved.push(code);
+                    continue;
+                },
+            },
+            TargetAirportSpec::Region(region) => {
+                if !db.has_kinds() {
+                    let field = if region.types.is_some() {
+                        Some("types")
+                    } else if region.limit.is_some() {
+                        Some("limit")
+                    } else {
+                        None
+                    };
+                    if let Some(field) = field {
+                        return Err(FlightPackError::Decode {
+                            source: "airports".into(),
+                            field: format!("[{}].{}", i, field),
+                            message: "Airport types are unknown in the active Airport Database \
+                                (load OurAirports airports.csv first)".into(),
+                        });
+                    }
+                }
+                let found: Vec<AirportRefer> = region.expand(&db);
+                if found.is_empty() {
+                    unresolved.push(region.label());
+                    continue;
+                }
+                found.into_iter().map(TargetAirportRawData::from).collect()
+            },
+        };
+
+        for raw in resolved {
+            if !airports.iter().any(|a| a.icao == raw.icao) {
+                airports.push(raw);
+            }
+        }
+    }
+
+    if !unresolved.is_empty() {
+        return Err(FlightPackError::UnresolvedAirports { codes: unresolved });
+    }
+
+    Ok(airports)
 }
 
 /// Once converted from `TargetAirportRawData`,
@@ -58,6 +325,7 @@ pub struct Airport {
     pub city: String,
     pub country: String,
     pub coord: GeoCoord,
+    pub details: AirportDetails,
     pub radius: f64,
     pub pos: PointCoord,
     pub text_width: f64,
@@ -79,6 +347,7 @@ impl Airport {
             city: raw.city,
             country: raw.country,
             coord: raw.coord,
+            details: raw.details,
             radius: 0_f64,
             pos: PointCoord::default(),
             text_width: 0_f64,
@@ -116,20 +385,14 @@ impl Airport {
             format!("{}px {}", font_size, FONT_FAMILY).as_str()
         );
 
-        let (width, height): (f64, f64) = ctx
-            .measure_text(&self.get_airport_text())
-            .map_or(
-                (
-                    AIRPORT_TEXT_WIDTH_DEFAULT,
-                    AIRPORT_TEXT_HEIGHT_DEFAULT
-                ),
-                |metrics: TextMetrics| {
-                    let h = metrics.actual_bounding_box_ascent() +
-                        metrics.actual_bounding_box_descent();
-                    let w = metrics.width();
-                    (w, h)
-                }
-            );
+        let (width, height): (f64, f64) = measure_text(
+            ctx,
+            &self.get_airport_text(),
+            (
+                AIRPORT_TEXT_WIDTH_DEFAULT,
+                AIRPORT_TEXT_HEIGHT_DEFAULT
+            ),
+        );
 
         ctx.restore();
 
@@ -138,18 +401,14 @@ impl Airport {
     }
 
     // Updating the airport positions.
-    // Positions need Mercator conversions.
+    // Positions need Mercator conversions (`projection`).
     // This is called in `Manager::update()`.
     pub fn update(
         &mut self,
-        canvas: &Canvas,
-        bounds: &LatLngBounds,
+        canvas: &Size,
+        projection: &dyn Projection,
     ) {
-        self.pos = get_mercator_position(
-            &canvas.size(),
-            bounds,
-            &self.coord,
-        );
+        self.pos = projection.project(&self.coord);
 
         let mut x: f64 = self.pos.x - (self.text_width * 0.8);
 
@@ -165,6 +424,24 @@ impl Airport {
         self.text_pos.y = self.pos.y - self.text_height;
     }
 
+    /// How far `point` is from the dot or the label
+    /// (0.0 when right on them), using the positions
+    /// calculated in `update()`.
+    pub fn hit_distance(&self, point: &PointCoord) -> f64 {
+        let dot: f64 = (point.distance(&self.pos) - self.radius).max(0.0);
+
+        // `text_pos` is where the baseline begins.
+        let left: f64 = self.text_pos.x;
+        let right: f64 = left + self.text_width;
+        let top: f64 = self.text_pos.y - self.text_height;
+        let bottom: f64 = self.text_pos.y;
+
+        let dx: f64 = (left - point.x).max(point.x - right).max(0.0);
+        let dy: f64 = (top - point.y).max(point.y - bottom).max(0.0);
+
+        dot.min(dx.hypot(dy))
+    }
+
     // Called in `Manager::draw()`.
     pub fn draw(&self, ctx: &CanvasRenderingContext2d) {
         let font_style: String =
@@ -199,3 +476,133 @@ impl Airport {
         ctx.restore();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specs(json: &str) -> Vec<TargetAirportSpec> {
+        decode_target_airports(serde_json::from_str(json).unwrap(), "airports").unwrap()
+    }
+
+    #[test]
+    fn resolves_codes_and_full_airports() {
+        let airports = resolve_target_airports(specs(r#"[
+            "SIN",
+            "vhhh",
+            {
+                "icao": "RPLL", "iata": "MNL", "name": "Ninoy Aquino International",
+                "city": "Manila", "country": "Philippines",
+                "coord": { "lat": 14.508, "lng": 121.019 }
+            },
+            "WSSS"
+        ]"#)).unwrap();
+
+        let icaos: Vec<&str> = airports.iter().map(|a| a.icao.as_str()).collect();
+        assert_eq!(icaos, vec!["WSSS", "VHHH", "RPLL"]);
+        assert_eq!(airports[0].name, "Singapore Changi");
+        assert_eq!(airports[0].details.tz.as_deref(), Some("Asia/Singapore"));
+        // Filled from the database even when given in full.
+        assert_eq!(airports[2].details.tz.as_deref(), Some("Asia/Manila"));
+    }
+
+    #[test]
+    fn expands_regions() {
+        let airports = resolve_target_airports(specs(r#"[
+            { "country": "Singapore" },
+            { "country": "hk" },
+            "HKG"
+        ]"#)).unwrap();
+
+        let icaos: Vec<&str> = airports.iter().map(|a| a.icao.as_str()).collect();
+        assert!(icaos.contains(&"WSSS"));
+        assert!(icaos.contains(&"VHHH"));
+        // VHHH only once.
+        assert_eq!(icaos.iter().filter(|&&icao| icao == "VHHH").count(), 1);
+        assert!(airports.iter().all(|a| matches!(a.country.as_str(), "Singapore" | "Hong Kong")));
+    }
+
+    #[test]
+    fn types_and_limit_need_a_database_knowing_them() {
+        let field = |json: &str| match resolve_target_airports(specs(json)).unwrap_err() {
+            FlightPackError::Decode { field, message, .. } => {
+                assert!(message.contains("airports.csv"));
+                field
+            },
+            err => panic!("Expected a decode error: {:?}", err),
+        };
+
+        assert_eq!(field(r#"["SIN", { "country": "SG", "types": ["large_airport"] }]"#), "[1].types");
+        assert_eq!(field(r#"["SIN", { "country": "SG", "limit": 1 }]"#), "[1].limit");
+    }
+
+    #[test]
+    fn limits_by_type_then_scheduled_service() {
+        let db = AirportDatabase::from_ourairports_csv("\
+ident,type,name,latitude_deg,longitude_deg,iso_country,scheduled_service,iata_code
+RJAA,large_airport,Narita International Airport,35.764,140.386,JP,yes,NRT
+RJAF,medium_airport,Matsumoto Airport,36.166,137.923,JP,yes,MMJ
+RJAH,medium_airport,Hyakuri Airport,36.181,140.415,JP,no,IBR
+RJBB,large_airport,Kansai International Airport,34.427,135.244,JP,yes,KIX
+RJTT,large_airport,Tokyo Haneda International Airport,35.552,139.78,JP,yes,HND
+").unwrap();
+
+        let region = serde_json::from_str::<RegionSelector>(r#"{ "country": "JP", "limit": 4 }"#).unwrap();
+        let icaos: Vec<String> = region.expand(&db).into_iter().map(|a| a.icao).collect();
+        assert_eq!(icaos, vec!["RJAA", "RJBB", "RJTT", "RJAF"]);
+    }
+
+    #[test]
+    fn filters_regions_by_type() {
+        let db = AirportDatabase::from_ourairports_csv("\
+ident,type,name,latitude_deg,longitude_deg,iso_country
+WSSS,large_airport,Changi Airport,1.35019,103.994003,SG
+WSSL,small_airport,Seletar Airport,1.41695,103.86799,SG
+WSAP,medium_airport,Paya Lebar Air Base,1.36042,103.91,SG
+").unwrap();
+
+        let region = |json: &str| serde_json::from_str::<RegionSelector>(json).unwrap();
+        let icaos = |airports: Vec<AirportRefer>| -> Vec<String> {
+            airports.into_iter().map(|a| a.icao).collect()
+        };
+
+        assert_eq!(
+            icaos(region(r#"{ "country": "SG", "types": ["small_airport", "medium_airport"] }"#).expand(&db)),
+            vec!["WSAP", "WSSL"],
+        );
+        assert_eq!(icaos(region(r#"{ "country": "SG", "limit": 2 }"#).expand(&db)), vec!["WSSS", "WSAP"]);
+        assert!(region("{}").expand(&db).is_empty());
+        assert!(serde_json::from_str::<RegionSelector>(r#"{ "contry": "SG" }"#).is_err());
+    }
+
+    #[test]
+    fn bad_airports_report_field_path() {
+        let field = |json: &str| {
+            match decode_target_airports(serde_json::from_str(json).unwrap(), "airports") {
+                Err(FlightPackError::Decode { source, field, .. }) => {
+                    assert_eq!(source, "airports");
+                    field
+                },
+                other => panic!("Expected a decode error: {:?}", other),
+            }
+        };
+
+        assert_eq!(field(r#"["SIN", {
+            "icao": "RPLL", "iata": "MNL", "name": "Ninoy Aquino International",
+            "city": "Manila", "country": "Philippines",
+            "coord": { "lat": "north", "lng": 121.019 }
+        }]"#), "[1].coord.lat");
+        assert_eq!(field(r#"[{ "icao": "RPLL", "iata": "MNL" }]"#), "[0]");
+        assert_eq!(field(r#"["SIN", { "country": "SG", "limit": "three" }]"#), "[1].limit");
+        assert_eq!(field("[42]"), "[0]");
+    }
+
+    #[test]
+    fn lists_all_unresolved_codes() {
+        let err = resolve_target_airports(specs(r#"["SIN", "XXX", { "country": "Atlantis" }, "ZZZZ"]"#))
+            .unwrap_err();
+        assert_eq!(err, FlightPackError::UnresolvedAirports {
+            codes: vec!["XXX".into(), "country:Atlantis".into(), "ZZZZ".into()],
+        });
+    }
+}
@@ -9,21 +9,15 @@ use chrono::{
     Duration,
 };
 use serde::de;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use web_sys::console;
 
 use crate::aviation::reference::{
     AirportRefer,
     lookup_airport_database,
 };
-use crate::constants::{
-    DUMMY,
-    AERO_API_URL,
-    AERO_API_KEY,
-};
-use crate::request::fetch;
-use crate::utils::get_json;
+use crate::aviation::status::{FlightStatus, diverted_to_icao};
 
 /// Deserialiation rules for date/time in arrival/departure information.
 fn from_rfc3339_z<'de, D>(d: D) -> Result<Option<DateTime<Utc>>, D::Error>
@@ -46,7 +40,7 @@ where D: de::Deserializer<'de>,
 
 /// Once extracted from `AeroArrivalsRawData`,
 /// this is the data structure we want for the app.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AirportArrival {
     pub id: String, // (ORIGINAL) fa_flight_id
     pub icao: String, // (ORIGINAL) ident_icao (in `Option`)
@@ -70,19 +64,55 @@ pub struct AirportArrival {
 
     pub progress_percent: i32,
     pub route_distance: i32, // (ORIGINAL) Option<route_distance>
+
+    // In seconds (negative when early).
+    pub departure_delay: Option<i32>,
+    pub arrival_delay: Option<i32>,
+
+    pub status: FlightStatus,
+
+    // Marketing idents of the same physical flight.
+    pub codeshares: Vec<String>, // ICAO (e.g. `UAL9416`)
+    pub codeshares_iata: Vec<String>, // IATA (e.g. `UA9416`)
+
+    pub aircraft_type: Option<String>, // ICAO designator (e.g. `B77W`)
 }
 
-/// This is how arrival/departure information look like
+/// This is how arrival information look like
 /// when received from FlightAware API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AeroArrivalsRawData {
-    // links: AeroLinkRawData, // NOT IN USE
-    // num_pages: i32, // NOT IN USE
+    pub links: Option<AeroLinkRawData>,
+    #[serde(default)]
+    pub num_pages: i32,
     pub arrivals: Vec<AeroArrivalsActualRawData>,
 }
 
+/// This is how departure information look like
+/// when received from FlightAware API.
+/// Each flight in `departures` has exactly
+/// the same shape as the ones in `arrivals`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AeroDeparturesRawData {
+    pub links: Option<AeroLinkRawData>,
+    #[serde(default)]
+    pub num_pages: i32,
+    pub departures: Vec<AeroArrivalsActualRawData>,
+}
+
+/// This is how a flight detail (`/flights/{id}`)
+/// looks like when received from FlightAware API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AeroFlightsRawData {
+    pub links: Option<AeroLinkRawData>,
+    #[serde(default)]
+    pub num_pages: i32,
+    pub flights: Vec<AeroArrivalsActualRawData>,
+}
+
 /// The above raw data has a nested structure, but we
-/// only want `arrivals`, and this is how it looks like.
+/// only want `arrivals` (or `departures`),
+/// and this is how it looks like.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AeroArrivalsActualRawData {
     // Either the operator code followed by the flight number
@@ -155,16 +185,16 @@ pub struct AeroArrivalsActualRawData {
     //  Information for this flight's destination airport.
     pub destination: AeroAirportRawData,
 
-    // Arrival delay (in seconds) based on either actual
-    // or estimated gate arrival time. If gate time
-    // is unavailable then based on runway arrival time.
-    // A negative value indicates the flight is early.
-    pub departure_delay: Option<i32>,
-
     // Departure delay (in seconds) based on either actual
     // or estimated gate departure time. If gate time
     // is unavailable then based on runway departure time.
     // A negative value indicates the flight is early.
+    pub departure_delay: Option<i32>,
+
+    // Arrival delay (in seconds) based on either actual
+    // or estimated gate arrival time. If gate time
+    // is unavailable then based on runway arrival time.
+    // A negative value indicates the flight is early.
     pub arrival_delay: Option<i32>,
 
     // Runway-to-runway filed duration (seconds).
@@ -275,13 +305,17 @@ impl AeroArrivalsActualRawData {
     /// (1) Check if the origin/destination airports are valid,
     /// (2) Check if these airports are the ones plotted on Google Map, and
     /// (3) Check if the arrival/departure falls under the current time window.
+    ///
+    /// When `recorded` is `true` (data from bundled fixtures),
+    /// times are shifted so that the flights happen on
+    /// the same day as `now` (which is `Clock::now()`).
     #[allow(clippy::unnecessary_unwrap)]
     pub fn extract(
         &self,
         airport_icaos: &[String],
+        recorded: bool,
+        now: DateTime<Utc>,
     ) -> Option<AirportArrival> {
-        let now: DateTime<Utc> = Utc::now();
-
         let sec_1: i64 = Utc.ymd(
             now.year(),
             now.month(),
@@ -299,8 +333,22 @@ impl AeroArrivalsActualRawData {
                 None
             };
 
+        let status: FlightStatus = FlightStatus::from_raw(self);
+
+        // Diverted flights are drawn to where they actually
+        // went (when we can tell from `status`).
+        let diverted_to: Option<AirportRefer> =
+            if status == FlightStatus::Diverted {
+                diverted_to_icao(&self.status)
+                    .and_then(|icao| lookup_airport_database(&icao))
+            } else {
+                None
+            };
+
         let dest_airport: Option<AirportRefer> =
-            if self.destination.code_icao.is_some() {
+            if diverted_to.is_some() {
+                diverted_to
+            } else if self.destination.code_icao.is_some() {
                 lookup_airport_database(
                     &self.clone().destination.code_icao.unwrap()
                 )
@@ -309,8 +357,9 @@ impl AeroArrivalsActualRawData {
             };
 
         // General checks on the fields.
-        if !self.cancelled &&
-            self.ident_icao.is_some() &&
+        // (Cancelled flights are kept, and whether
+        // to show them is up to `StatusOptions`.)
+        if self.ident_icao.is_some() &&
             self.ident_iata.is_some() &&
             orig_airport.is_some() &&
             dest_airport.is_some() &&
@@ -329,8 +378,8 @@ impl AeroArrivalsActualRawData {
 
             let mut delta = Duration::seconds(0);
 
-            // For DUMMY, we manipulate time.
-            if DUMMY {
+            // For recorded data, we manipulate time.
+            if recorded {
                 let d: DateTime<Utc> = self.scheduled_out.unwrap();
                 let sec_0: i64 = Utc.ymd(
                     d.year(),
@@ -355,10 +404,13 @@ impl AeroArrivalsActualRawData {
             // in the JS given list of airports. Also,
             // check if arrival/departure falls under
             // the current time window.
+            // For diverted flights, it is the destination
+            // originally planned which must be in the list
+            // (where they actually went is only for drawing).
             if airport_icaos.to_owned().iter().any(|icao| {
                 icao == orig_airport.icao.as_str()
             }) && airport_icaos.to_owned().iter().any(|icao| {
-                icao == dest_airport.icao.as_str()
+                self.destination.code_icao.as_deref() == Some(icao.as_str())
             }) {
                 // actual_out < now && scheduled_in > now
 
@@ -372,6 +424,11 @@ impl AeroArrivalsActualRawData {
                 let progress_percent: i32 =
                     clone.progress_percent.unwrap_or(0);
                 let route_distance: i32 = clone.route_distance.unwrap();
+                let aircraft_type: Option<String> = clone.aircraft_type;
+                let codeshares: Vec<String> = clone.codeshares;
+                let codeshares_iata: Vec<String> = clone.codeshares_iata;
+                let departure_delay: Option<i32> = clone.departure_delay;
+                let arrival_delay: Option<i32> = clone.arrival_delay;
 
                 result = Some(
                     AirportArrival {
@@ -390,6 +447,12 @@ impl AeroArrivalsActualRawData {
                         estimated_in,
                         progress_percent,
                         route_distance,
+                        departure_delay,
+                        arrival_delay,
+                        status,
+                        codeshares,
+                        codeshares_iata,
+                        aircraft_type,
                     }
                 );
             }
@@ -414,93 +477,242 @@ pub struct AeroAirportRawData {
     pub airport_info_url: Option<String>,
 }
 
-lazy_static! {
-    #[derive(Debug)]
-    pub static ref DUMMY_ARRIVALS: HashMap<String, AeroArrivalsRawData> = {
-        let mut hashmap = HashMap::new();
+/// Each page of arrivals/departures comes with `links`.
+/// When there are more pages, `next` holds the path
+/// (relative to `AERO_API_URL`) for the following page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AeroLinkRawData {
+    pub next: Option<String>,
+}
 
-        hashmap.insert(
-            "VVTS".into(), // SGN (Tan Son Nhat, Saigon)
-            get_json::<AeroArrivalsRawData>(
-                include_str!("../../json/arrivals_saigon.json")
-            )
-        );
+impl AeroArrivalsActualRawData {
+    /// Whether `ident_icao` is of the carrier actually
+    /// flying the aircraft (rather than a marketing one).
+    pub fn is_operating(&self) -> bool {
+        match (&self.ident_icao, &self.operator_icao) {
+            (Some(ident), Some(operator)) => ident.starts_with(operator.as_str()),
+            _ => false,
+        }
+    }
+}
 
-        hashmap.insert(
-            "RCTP".into(), // TPE (Taiwan Taoyuan, Taipei)
-            get_json::<AeroArrivalsRawData>(
-                include_str!("../../json/arrivals_taiwan.json")
-            )
-        );
+/// The same physical flight may come more than once
+/// (from arrivals of one airport and departures of another,
+/// or once for each marketing carrier). Records are collapsed
+/// into one for each `fa_flight_id` (keeping the first-seen
+/// order), where the operating carrier's record becomes
+/// the primary, and all the other idents become codeshares.
+pub fn collapse_codeshares(
+    records: impl IntoIterator<Item = AeroArrivalsActualRawData>,
+) -> Vec<AeroArrivalsActualRawData> {
+    let mut order: Vec<String> = vec![];
+    let mut groups: HashMap<String, Vec<AeroArrivalsActualRawData>> = HashMap::new();
+
+    records.into_iter().for_each(|record| {
+        let id: String = record.fa_flight_id.clone();
+        if !groups.contains_key(&id) {
+            order.push(id.clone());
+        }
+        groups.entry(id).or_default().push(record);
+    });
+
+    order.iter()
+        .filter_map(|id| groups.remove(id))
+        .map(|group| {
+            let primary: usize = group.iter()
+                .position(|r| r.is_operating())
+                .unwrap_or(0);
+
+            let mut merged: AeroArrivalsActualRawData = group[primary].clone();
+            let mut codeshares: Vec<String> = vec![];
+            let mut codeshares_iata: Vec<String> = vec![];
+
+            group.iter().for_each(|r| {
+                codeshares.extend(r.ident_icao.iter().cloned());
+                codeshares.extend(r.codeshares.iter().cloned());
+                codeshares_iata.extend(r.ident_iata.iter().cloned());
+                codeshares_iata.extend(r.codeshares_iata.iter().cloned());
+            });
+
+            merged.codeshares = dedup_idents(codeshares, &merged.ident_icao);
+            merged.codeshares_iata = dedup_idents(codeshares_iata, &merged.ident_iata);
+            merged
+        })
+        .collect()
+}
 
-        hashmap.insert(
-            "VHHH".into(), // (Hong Kong)
-            get_json::<AeroArrivalsRawData>(
-                include_str!("../../json/arrivals_hongkong.json")
-            )
-        );
+// Keeps the order, but removes duplicates and `primary`.
+fn dedup_idents(idents: Vec<String>, primary: &Option<String>) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    idents.into_iter().for_each(|ident| {
+        if primary.as_ref() != Some(&ident) && !result.contains(&ident) {
+            result.push(ident);
+        }
+    });
+    result
+}
 
-        hashmap.insert(
-            "WSSS".into(), // SIN (Changi, Singapore)
-            get_json::<AeroArrivalsRawData>(
-                include_str!("../../json/arrivals_changi.json")
-            )
-        );
+/// Both `AeroArrivalsRawData` and `AeroDeparturesRawData`
+/// come in pages, and this is what we need
+/// to follow the cursors and concatenate the pages.
+pub trait AeroPagedRawData: Clone + DeserializeOwned {
+    fn next_page(&self) -> Option<String>;
+    fn append_page(&mut self, page: Self);
+
+    /// `true` when there are still pages left
+    /// (meaning, we stopped at the page limit).
+    fn has_more(&self) -> bool {
+        self.next_page().is_some()
+    }
+}
 
-        hashmap.insert(
-            "RPLL".into(), // MNL (Manila, Philippines)
-            get_json::<AeroArrivalsRawData>(
-                include_str!("../../json/arrivals_manila.json")
-            )
-        );
+impl AeroPagedRawData for AeroArrivalsRawData {
+    fn next_page(&self) -> Option<String> {
+        self.links.as_ref().and_then(|links| links.next.clone())
+    }
 
-        hashmap
-    };
+    fn append_page(&mut self, page: Self) {
+        self.arrivals.extend(page.arrivals);
+        self.links = page.links;
+        self.num_pages += page.num_pages;
+    }
 }
 
-pub async fn fetch_arrivals(icao: String) ->
-    Result<AeroArrivalsRawData, String>
-{
-    let icao = icao.as_str();
-
-    if DUMMY {
-        match DUMMY_ARRIVALS.get(icao) {
-            Some(arrival) => {
-                Ok(arrival.clone())
-            },
-            None => {
-                Err("Failed to get \"DUMMY_ARRIVALS\"".into())
-            },
-        }
-    } else {
-        let url: String = format!(
-            "{}/airports/{}/flights/arrivals",
-            AERO_API_URL,
-            icao,
+impl AeroPagedRawData for AeroDeparturesRawData {
+    fn next_page(&self) -> Option<String> {
+        self.links.as_ref().and_then(|links| links.next.clone())
+    }
+
+    fn append_page(&mut self, page: Self) {
+        self.departures.extend(page.departures);
+        self.links = page.links;
+        self.num_pages += page.num_pages;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::source::FIXTURE_PAGES;
+
+    fn sia917() -> AeroArrivalsActualRawData {
+        let page: AeroArrivalsRawData = serde_json::from_str(
+            FIXTURE_PAGES["/airports/WSSS/flights/arrivals"]
+        ).unwrap();
+
+        page.arrivals
+            .into_iter()
+            .find(|f| f.ident == "SIA917")
+            .unwrap()
+    }
+
+    #[test]
+    fn extract_shifts_recorded_flights_to_clock_day() {
+        let icaos: Vec<String> = vec!["RPLL".into(), "WSSS".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let arrival = sia917().extract(&icaos, true, now).unwrap();
+
+        assert_eq!(
+            arrival.actual_out,
+            Utc.with_ymd_and_hms(2026, 10, 17, 6, 15, 0).unwrap(),
         );
+        assert_eq!(
+            arrival.scheduled_in,
+            Utc.with_ymd_and_hms(2026, 10, 17, 9, 50, 0).unwrap(),
+        );
+    }
 
-        let mut headers: HashMap<String, String> = HashMap::new();
+    #[test]
+    fn extract_keeps_live_flights_as_is() {
+        let icaos: Vec<String> = vec!["RPLL".into(), "WSSS".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let arrival = sia917().extract(&icaos, false, now).unwrap();
 
-        headers.insert(
-            "X-Apikey".into(),
-            AERO_API_KEY.to_string(),
+        assert_eq!(
+            arrival.actual_out,
+            Utc.with_ymd_and_hms(2022, 8, 24, 6, 15, 0).unwrap(),
         );
+    }
 
-        match fetch(
-            url.as_str(),
-            Some(headers),
-        ).await {
-            Ok(json) => {
-                Ok(json.into_serde().unwrap())
-            }
-            Err(e) => {
-                let default_err: String = String::from("Error");
-                let err: String = e.as_string().unwrap_or(default_err);
-                console::error_1(&(
-                    format!("{} for: {}", err, url).into()
-                ));
-                Err(err)
-            }
+    #[test]
+    fn extract_rejects_flights_outside_target_airports() {
+        let icaos: Vec<String> = vec!["WSSS".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        assert!(sia917().extract(&icaos, true, now).is_none());
+    }
+
+    #[test]
+    fn extract_keeps_cancelled_flights_with_status() {
+        let icaos: Vec<String> = vec!["RPLL".into(), "WSSS".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let mut raw = sia917();
+        raw.cancelled = true;
+
+        let arrival = raw.extract(&icaos, true, now).unwrap();
+        assert_eq!(arrival.status, FlightStatus::Cancelled);
+    }
+
+    #[test]
+    fn extract_draws_diverted_flights_to_actual_destination() {
+        let icaos: Vec<String> = vec!["RPLL".into(), "WSSS".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let mut raw = sia917();
+        raw.diverted = true;
+        raw.status = "Diverted to VHHH".into();
+
+        let arrival = raw.extract(&icaos, true, now).unwrap();
+        assert_eq!(arrival.status, FlightStatus::Diverted);
+        assert_eq!(arrival.dest_airport.icao, "VHHH");
+    }
+
+    #[test]
+    fn extract_rejects_diverted_flights_planned_elsewhere() {
+        // Planned for WSSS (not a target) whether
+        // or not where it went can be told.
+        let icaos: Vec<String> = vec!["RPLL".into(), "VHHH".into()];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+
+        for status in ["Diverted to VHHH", "Diverted"] {
+            let mut raw = sia917();
+            raw.diverted = true;
+            raw.status = status.into();
+            assert!(raw.extract(&icaos, true, now).is_none());
         }
     }
+
+    #[test]
+    fn status_follows_actual_timestamps() {
+        let mut raw = sia917();
+        raw.actual_on = None;
+        raw.actual_in = None;
+        raw.actual_off = None;
+        assert_eq!(FlightStatus::from_raw(&raw), FlightStatus::Taxiing);
+
+        raw.actual_out = None;
+        assert_eq!(FlightStatus::from_raw(&raw), FlightStatus::Scheduled);
+    }
+
+    #[test]
+    fn collapse_codeshares_keeps_operating_carrier() {
+        let operating = sia917();
+
+        let mut marketing = sia917();
+        marketing.ident = "UAL9416".into();
+        marketing.ident_icao = Some("UAL9416".into());
+        marketing.ident_iata = Some("UA9416".into());
+        marketing.codeshares = vec!["SIA917".into(), "DLH9771".into()];
+
+        let mut other = sia917();
+        other.fa_flight_id = "OTHER".into();
+
+        let flights = collapse_codeshares(vec![marketing, other, operating]);
+        assert_eq!(flights.len(), 2);
+        assert_eq!(flights[1].fa_flight_id, "OTHER");
+
+        let flight = &flights[0];
+        assert_eq!(flight.ident_icao.as_deref(), Some("SIA917"));
+        // ANZ3437 (NZ3437) comes from the operating record.
+        assert_eq!(flight.codeshares, vec!["UAL9416", "DLH9771", "ANZ3437"]);
+        assert_eq!(flight.codeshares_iata, vec!["UA9416", "NZ3437"]);
+    }
 }
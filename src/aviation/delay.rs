@@ -0,0 +1,222 @@
+/// Flights are colored by how late they are.
+/// Delays (in seconds, negative when early) come from
+/// `arrival_delay` of FlightAware API (or `departure_delay`
+/// when not available), and are put into one of
+/// the buckets using `DelayThresholds` (in minutes).
+///
+/// Say, thresholds are `early: 5, minor: 15, major: 60`:
+///
+/// - Early:   delay < -5 min
+/// - On time: -5 min <= delay < 15 min
+/// - Minor:   15 min <= delay < 60 min
+/// - Major:   60 min <= delay
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{
+    DELAY_COLOR_EARLY,
+    DELAY_COLOR_ON_TIME,
+    DELAY_COLOR_MINOR,
+    DELAY_COLOR_MAJOR,
+    DELAY_THRESHOLD_MAX_MINUTES,
+};
+use crate::error::FlightPackError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DelayBucket {
+    Early,
+    #[default]
+    OnTime,
+    Minor,
+    Major,
+}
+
+impl DelayBucket {
+    pub const ALL: [DelayBucket; 4] = [
+        DelayBucket::Early,
+        DelayBucket::OnTime,
+        DelayBucket::Minor,
+        DelayBucket::Major,
+    ];
+
+    pub fn color(&self) -> &'static str {
+        match self {
+            DelayBucket::Early => DELAY_COLOR_EARLY,
+            DelayBucket::OnTime => DELAY_COLOR_ON_TIME,
+            DelayBucket::Minor => DELAY_COLOR_MINOR,
+            DelayBucket::Major => DELAY_COLOR_MAJOR,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DelayBucket::Early => "Early",
+            DelayBucket::OnTime => "On time",
+            DelayBucket::Minor => "Minor delay",
+            DelayBucket::Major => "Major delay",
+        }
+    }
+}
+
+/// All in minutes. `early` is how early (a positive number)
+/// a flight must be to be called early.
+/// JS may give any of them (see `App::set_delay_thresholds`),
+/// and they must pass `validate` before use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DelayThresholds {
+    pub early: i32,
+    pub minor: i32,
+    pub major: i32,
+}
+
+impl Default for DelayThresholds {
+    fn default() -> Self {
+        DelayThresholds {
+            early: 5,
+            minor: 15,
+            major: 60,
+        }
+    }
+}
+
+/// Each bucket with its range (in minutes)
+/// so that UI can draw a legend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DelayLegendEntry {
+    pub bucket: DelayBucket,
+    pub label: String,
+    pub color: String,
+    pub min_minutes: Option<i32>, // Inclusive
+    pub max_minutes: Option<i32>, // Exclusive
+}
+
+impl DelayThresholds {
+    /// All must be between 0 and `DELAY_THRESHOLD_MAX_MINUTES`,
+    /// and `minor` may not exceed `major` (or `Minor`
+    /// would never be reached). `source` tells where
+    /// they came from for the error.
+    pub fn validate(&self, source: &str) -> Result<(), FlightPackError> {
+        let invalid = |field: &str, message: String| FlightPackError::Decode {
+            source: source.into(),
+            field: field.into(),
+            message,
+        };
+
+        for (field, minutes) in [
+            ("early", self.early),
+            ("minor", self.minor),
+            ("major", self.major),
+        ] {
+            if !(0..=DELAY_THRESHOLD_MAX_MINUTES).contains(&minutes) {
+                return Err(invalid(field, format!(
+                    "{} minutes is out of range (0 to {})",
+                    minutes,
+                    DELAY_THRESHOLD_MAX_MINUTES,
+                )));
+            }
+        }
+
+        if self.minor > self.major {
+            return Err(invalid("minor", format!(
+                "minor ({}) may not exceed major ({})",
+                self.minor,
+                self.major,
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// `delay` is in seconds (as FlightAware gives).
+    /// Unknown delays are considered on time.
+    pub fn bucket(&self, delay: Option<i32>) -> DelayBucket {
+        // In `i64` so that large thresholds never overflow.
+        let delay: i64 = match delay {
+            Some(delay) => delay as i64,
+            None => return DelayBucket::OnTime,
+        };
+
+        if delay < -(self.early as i64) * 60 {
+            DelayBucket::Early
+        } else if delay < self.minor as i64 * 60 {
+            DelayBucket::OnTime
+        } else if delay < self.major as i64 * 60 {
+            DelayBucket::Minor
+        } else {
+            DelayBucket::Major
+        }
+    }
+
+    pub fn legend(&self) -> Vec<DelayLegendEntry> {
+        DelayBucket::ALL.iter()
+            .map(|bucket| {
+                let (min_minutes, max_minutes) = match bucket {
+                    DelayBucket::Early => (None, Some(-self.early)),
+                    DelayBucket::OnTime => (Some(-self.early), Some(self.minor)),
+                    DelayBucket::Minor => (Some(self.minor), Some(self.major)),
+                    DelayBucket::Major => (Some(self.major), None),
+                };
+
+                DelayLegendEntry {
+                    bucket: *bucket,
+                    label: bucket.label().into(),
+                    color: bucket.color().into(),
+                    min_minutes,
+                    max_minutes,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_by_thresholds() {
+        let t = DelayThresholds::default();
+        assert_eq!(t.bucket(Some(-600)), DelayBucket::Early);
+        assert_eq!(t.bucket(Some(-301)), DelayBucket::Early);
+        assert_eq!(t.bucket(Some(-300)), DelayBucket::OnTime);
+        assert_eq!(t.bucket(Some(0)), DelayBucket::OnTime);
+        assert_eq!(t.bucket(None), DelayBucket::OnTime);
+        assert_eq!(t.bucket(Some(900)), DelayBucket::Minor);
+        assert_eq!(t.bucket(Some(3599)), DelayBucket::Minor);
+        assert_eq!(t.bucket(Some(7323)), DelayBucket::Major);
+
+        let t = DelayThresholds { minor: 5, ..DelayThresholds::default() };
+        assert_eq!(t.bucket(Some(300)), DelayBucket::Minor);
+    }
+
+    #[test]
+    fn partial_thresholds_keep_defaults() {
+        let t: DelayThresholds = serde_json::from_str(r#"{ "major": 30 }"#).unwrap();
+        assert_eq!(t, DelayThresholds { major: 30, ..DelayThresholds::default() });
+
+        let legend = t.legend();
+        assert_eq!(legend.len(), 4);
+        assert_eq!(legend[2].bucket, DelayBucket::Minor);
+        assert_eq!((legend[2].min_minutes, legend[2].max_minutes), (Some(15), Some(30)));
+        assert_eq!(legend[3].max_minutes, None);
+    }
+
+    #[test]
+    fn invalid_thresholds_are_rejected() {
+        assert!(DelayThresholds::default().validate("delay_thresholds").is_ok());
+
+        let field = |t: DelayThresholds| match t.validate("delay_thresholds") {
+            Err(FlightPackError::Decode { field, .. }) => field,
+            other => panic!("Expected a decode error: {:?}", other),
+        };
+        assert_eq!(field(DelayThresholds { major: 10, ..DelayThresholds::default() }), "minor");
+        assert_eq!(field(DelayThresholds { early: -5, ..DelayThresholds::default() }), "early");
+        assert_eq!(field(DelayThresholds { major: i32::MAX, ..DelayThresholds::default() }), "major");
+
+        // Would overflow in `i32` seconds.
+        let t = DelayThresholds { early: i32::MAX, minor: i32::MAX, major: i32::MAX };
+        assert_eq!(t.bucket(Some(i32::MIN)), DelayBucket::OnTime);
+    }
+}
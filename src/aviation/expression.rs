@@ -0,0 +1,669 @@
+/// A small expression language for filtering flights.
+/// Analysts may type something like:
+///
+/// ```text
+/// operator == "CPA" && route_distance > 1000 && dest.country == "Singapore"
+/// ```
+///
+/// Expressions are parsed (and type checked) once
+/// with `Expression::parse`, then evaluated against
+/// each `Flight` with `Expression::matches`.
+///
+/// From the lowest precedence to the highest:
+///
+/// (1) `||`
+/// (2) `&&`
+/// (3) `!` (so `!status == "landed"` means `!(status == "landed")`)
+/// (4) `==`, `!=`, `<`, `<=`, `>`, `>=`
+/// (5) Parentheses, fields, and literals
+///     (`"text"` or `'text'`, numbers, `true`, `false` and `null`)
+///
+/// Text comparisons are case insensitive. `<`, `<=`,
+/// `>` and `>=` are only for numbers, and fields which
+/// may be unknown (e.g. `aircraft_type`) are `null`
+/// (which is only equal to `null`). `ident == "UA9416"`
+/// (and `iata`) matches codeshares as well.
+/// See `FLIGHT_FIELDS` and `AIRPORT_FIELDS` for the fields available.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::aviation::flight::Flight;
+use crate::aviation::reference::AirportRefer;
+
+/// Where and why the expression is wrong.
+/// `position` is the character offset (from 0).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpressionError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl ExpressionError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        ExpressionError {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at {})", self.message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Text,
+    Number,
+    Bool,
+    Null,
+}
+
+impl Type {
+    fn name(&self) -> &'static str {
+        match self {
+            Type::Text => "text",
+            Type::Number => "number",
+            Type::Bool => "true/false",
+            Type::Null => "null",
+        }
+    }
+}
+
+// Fields of flights with their types,
+// and whether they may be `null`.
+const FLIGHT_FIELDS: [(&str, Type, bool); 12] = [
+    ("ident", Type::Text, false), // SIA917 (or any codeshare)
+    ("iata", Type::Text, false), // SQ917 (or any codeshare)
+    ("operator", Type::Text, false), // SIA
+    ("airline", Type::Text, true), // Singapore Airlines
+    ("flight_number", Type::Text, false),
+    ("aircraft_type", Type::Text, true), // B77W
+    ("wake", Type::Text, true), // light, medium, heavy, super
+    ("status", Type::Text, false), // scheduled, taxiing, airborne, ...
+    ("route_distance", Type::Number, false), // Statute miles
+    ("delay", Type::Number, true), // Minutes
+    ("delay_bucket", Type::Text, false), // early, on_time, minor, major
+    ("progress", Type::Number, false), // Percent
+];
+
+// Fields of airports, prefixed with `origin.` (or `orig.`)
+// and `dest.` (or `destination.`).
+const AIRPORT_FIELDS: [(&str, Type, bool); 7] = [
+    ("icao", Type::Text, false),
+    ("iata", Type::Text, false),
+    ("name", Type::Text, false),
+    ("city", Type::Text, false),
+    ("country", Type::Text, false),
+    ("lat", Type::Number, false),
+    ("lng", Type::Number, false),
+];
+
+fn field_type(path: &str) -> Option<(Type, bool)> {
+    let (fields, field): (&[(&str, Type, bool)], &str) = match path.split_once('.') {
+        Some((airport, field)) => {
+            airport_of(airport)?;
+            (&AIRPORT_FIELDS, field)
+        },
+        None => (&FLIGHT_FIELDS, path),
+    };
+
+    fields.iter()
+        .find(|(name, _, _)| *name == field)
+        .map(|(_, t, nullable)| (*t, *nullable))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AirportSide {
+    Origin,
+    Destination,
+}
+
+fn airport_of(name: &str) -> Option<AirportSide> {
+    match name {
+        "origin" | "orig" => Some(AirportSide::Origin),
+        "dest" | "destination" => Some(AirportSide::Destination),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.into())
+}
+
+fn serde_name<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(text))
+        .unwrap_or(Value::Null)
+}
+
+fn airport_value(airport: &AirportRefer, field: &str) -> Value {
+    match field {
+        "icao" => text(&airport.icao),
+        "iata" => text(&airport.iata),
+        "name" => text(&airport.name),
+        "city" => text(&airport.city),
+        "country" => text(&airport.country),
+        "lat" => Value::Number(airport.coord.lat),
+        "lng" => Value::Number(airport.coord.lng),
+        _ => Value::Null,
+    }
+}
+
+fn field_value(flight: &Flight, path: &str) -> Value {
+    if let Some((airport, field)) = path.split_once('.') {
+        return match airport_of(airport) {
+            Some(AirportSide::Origin) => airport_value(&flight.orig_airport, field),
+            Some(AirportSide::Destination) => airport_value(&flight.dest_airport, field),
+            None => Value::Null,
+        };
+    }
+
+    match path {
+        "ident" => text(&flight.icao),
+        "iata" => text(&flight.iata),
+        "operator" => text(&flight.operator),
+        "airline" => flight.airline.as_ref().map_or(Value::Null, |a| text(&a.name)),
+        "flight_number" => text(&flight.flight_number),
+        "aircraft_type" => flight.aircraft.as_ref().map_or(Value::Null, |a| text(&a.icao)),
+        "wake" => flight.aircraft.as_ref().map_or(Value::Null, |a| serde_name(&a.wake)),
+        "status" => serde_name(&flight.status),
+        "route_distance" => Value::Number(flight.route_distance as f64),
+        "delay" => flight.delay().map_or(Value::Null, |d| Value::Number(d as f64 / 60.0)),
+        "delay_bucket" => serde_name(&flight.delay_bucket),
+        "progress" => Value::Number(flight.progress * 100.0),
+        _ => Value::Null,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Field(String),
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    LeftParen,
+    RightParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Field(name) => format!("'{}'", name),
+            Token::Text(value) => format!("\"{}\"", value),
+            Token::Number(value) => format!("{}", value),
+            Token::Bool(value) => format!("{}", value),
+            Token::Null => "null".into(),
+            Token::Compare(op) => format!("'{}'", op.symbol()),
+            Token::And => "'&&'".into(),
+            Token::Or => "'||'".into(),
+            Token::Not => "'!'".into(),
+            Token::LeftParen => "'('".into(),
+            Token::RightParen => "')'".into(),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(Token, usize)> = vec![];
+    let mut i: usize = 0;
+
+    while i < chars.len() {
+        let c: char = chars[i];
+        let start: usize = i;
+        let next: Option<char> = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token: Token = match (c, next) {
+            ('&', Some('&')) => { i += 2; Token::And },
+            ('|', Some('|')) => { i += 2; Token::Or },
+            ('=', Some('=')) => { i += 2; Token::Compare(CompareOp::Eq) },
+            ('!', Some('=')) => { i += 2; Token::Compare(CompareOp::Ne) },
+            ('<', Some('=')) => { i += 2; Token::Compare(CompareOp::Le) },
+            ('>', Some('=')) => { i += 2; Token::Compare(CompareOp::Ge) },
+            ('<', _) => { i += 1; Token::Compare(CompareOp::Lt) },
+            ('>', _) => { i += 1; Token::Compare(CompareOp::Gt) },
+            ('!', _) => { i += 1; Token::Not },
+            ('(', _) => { i += 1; Token::LeftParen },
+            (')', _) => { i += 1; Token::RightParen },
+            ('"', _) | ('\'', _) => {
+                let end: usize = chars[i + 1..].iter()
+                    .position(|&q| q == c)
+                    .map(|p| i + 1 + p)
+                    .ok_or_else(|| ExpressionError::new("Unterminated text", start))?;
+                let value: String = chars[i + 1..end].iter().collect();
+                i = end + 1;
+                Token::Text(value)
+            },
+            _ if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value: f64 = literal.parse().map_err(|_| {
+                    ExpressionError::new(format!("Invalid number '{}'", literal), start)
+                })?;
+                Token::Number(value)
+            },
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() &&
+                    (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.as_str() {
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    "null" => Token::Null,
+                    _ => Token::Field(word),
+                }
+            },
+            _ => {
+                return Err(ExpressionError::new(
+                    format!("Unexpected character '{}'", c),
+                    start,
+                ));
+            },
+        };
+
+        tokens.push((token, start));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(CompareOp, Box<Node>, Box<Node>),
+    Field(String),
+    Literal(Value),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize, // Length of the source (for errors at the end)
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end, |(_, pos)| *pos)
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        match self.peek() {
+            Some(token) => ExpressionError::new(
+                format!("Unexpected {}", token.describe()),
+                self.position(),
+            ),
+            None => ExpressionError::new("Unexpected end of expression", self.end),
+        }
+    }
+
+    // Each returns the node, its type and where it begins.
+    fn or(&mut self) -> Result<(Node, Type, usize), ExpressionError> {
+        let mut lhs = self.and()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.index += 1;
+            let rhs = self.and()?;
+            expect_bool("||", &lhs)?;
+            expect_bool("||", &rhs)?;
+            lhs = (Node::Or(Box::new(lhs.0), Box::new(rhs.0)), Type::Bool, lhs.2);
+        }
+
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<(Node, Type, usize), ExpressionError> {
+        let mut lhs = self.not()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.index += 1;
+            let rhs = self.not()?;
+            expect_bool("&&", &lhs)?;
+            expect_bool("&&", &rhs)?;
+            lhs = (Node::And(Box::new(lhs.0), Box::new(rhs.0)), Type::Bool, lhs.2);
+        }
+
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<(Node, Type, usize), ExpressionError> {
+        if self.peek() == Some(&Token::Not) {
+            let start: usize = self.position();
+            self.index += 1;
+            let operand = self.not()?;
+            expect_bool("!", &operand)?;
+            return Ok((Node::Not(Box::new(operand.0)), Type::Bool, start));
+        }
+
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<(Node, Type, usize), ExpressionError> {
+        let lhs = self.primary()?;
+
+        let op: CompareOp = match self.peek() {
+            Some(Token::Compare(op)) => *op,
+            _ => return Ok(lhs),
+        };
+        let op_position: usize = self.position();
+        self.index += 1;
+
+        let rhs = self.primary()?;
+
+        match op {
+            CompareOp::Eq | CompareOp::Ne => {
+                if lhs.1 != rhs.1 && lhs.1 != Type::Null && rhs.1 != Type::Null {
+                    return Err(ExpressionError::new(
+                        format!(
+                            "Cannot compare {} with {}",
+                            lhs.1.name(),
+                            rhs.1.name(),
+                        ),
+                        op_position,
+                    ));
+                }
+            },
+            _ => {
+                if let Some((_, _, pos)) = [&lhs, &rhs].iter().find(|(_, t, _)| *t != Type::Number) {
+                    return Err(ExpressionError::new(
+                        format!("'{}' needs numbers on both sides", op.symbol()),
+                        *pos,
+                    ));
+                }
+            },
+        }
+
+        Ok((Node::Compare(op, Box::new(lhs.0), Box::new(rhs.0)), Type::Bool, lhs.2))
+    }
+
+    fn primary(&mut self) -> Result<(Node, Type, usize), ExpressionError> {
+        let start: usize = self.position();
+
+        let token: Token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.unexpected()),
+        };
+
+        let result = match token {
+            Token::LeftParen => {
+                self.index += 1;
+                let (node, t, _) = self.or()?;
+                if self.peek() != Some(&Token::RightParen) {
+                    return Err(ExpressionError::new("Expected ')'", self.position()));
+                }
+                (node, t, start)
+            },
+            Token::Field(name) => {
+                let (t, _) = field_type(&name).ok_or_else(|| {
+                    ExpressionError::new(format!("Unknown field '{}'", name), start)
+                })?;
+                (Node::Field(name), t, start)
+            },
+            Token::Text(value) => (Node::Literal(Value::Text(value)), Type::Text, start),
+            Token::Number(value) => (Node::Literal(Value::Number(value)), Type::Number, start),
+            Token::Bool(value) => (Node::Literal(Value::Bool(value)), Type::Bool, start),
+            Token::Null => (Node::Literal(Value::Null), Type::Null, start),
+            _ => return Err(self.unexpected()),
+        };
+
+        self.index += 1;
+        Ok(result)
+    }
+}
+
+fn expect_bool(op: &str, operand: &(Node, Type, usize)) -> Result<(), ExpressionError> {
+    if operand.1 == Type::Bool {
+        Ok(())
+    } else {
+        Err(ExpressionError::new(
+            format!("'{}' needs true/false, but got {}", op, operand.1.name()),
+            operand.2,
+        ))
+    }
+}
+
+/// A parsed (and type checked) expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            index: 0,
+            end: source.chars().count(),
+        };
+
+        let root = parser.or()?;
+
+        if parser.peek().is_some() {
+            return Err(parser.unexpected());
+        }
+
+        if root.1 != Type::Bool {
+            return Err(ExpressionError::new(
+                format!("Expression must be true/false, but got {}", root.1.name()),
+                root.2,
+            ));
+        }
+
+        Ok(Expression {
+            source: source.into(),
+            root: root.0,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, flight: &Flight) -> bool {
+        evaluate(&self.root, flight) == Value::Bool(true)
+    }
+}
+
+fn evaluate(node: &Node, flight: &Flight) -> Value {
+    match node {
+        Node::Or(lhs, rhs) => Value::Bool(
+            evaluate(lhs, flight) == Value::Bool(true) ||
+                evaluate(rhs, flight) == Value::Bool(true)
+        ),
+        Node::And(lhs, rhs) => Value::Bool(
+            evaluate(lhs, flight) == Value::Bool(true) &&
+                evaluate(rhs, flight) == Value::Bool(true)
+        ),
+        Node::Not(operand) => Value::Bool(evaluate(operand, flight) != Value::Bool(true)),
+        Node::Compare(op, lhs, rhs) => {
+            let matched: Option<bool> = match op {
+                CompareOp::Eq | CompareOp::Ne => ident_equality(flight, lhs, rhs),
+                _ => None,
+            };
+            Value::Bool(match matched {
+                Some(equal) => equal == (*op == CompareOp::Eq),
+                None => compare(*op, &evaluate(lhs, flight), &evaluate(rhs, flight)),
+            })
+        },
+        Node::Field(path) => field_value(flight, path),
+        Node::Literal(value) => value.clone(),
+    }
+}
+
+// `ident` (or `iata`) compared with a text is equal
+// when the text is any of `Flight::idents`.
+fn ident_equality(flight: &Flight, lhs: &Node, rhs: &Node) -> Option<bool> {
+    match (lhs, rhs) {
+        (Node::Field(field), Node::Literal(Value::Text(ident))) |
+        (Node::Literal(Value::Text(ident)), Node::Field(field))
+            if field == "ident" || field == "iata" =>
+        {
+            Some(flight.matches_ident(ident))
+        },
+        _ => None,
+    }
+}
+
+fn compare(op: CompareOp, lhs: &Value, rhs: &Value) -> bool {
+    let equal: bool = match (lhs, rhs) {
+        (Value::Text(a), Value::Text(b)) => a.eq_ignore_ascii_case(b),
+        (a, b) => a == b,
+    };
+
+    match (op, lhs, rhs) {
+        (CompareOp::Eq, _, _) => equal,
+        (CompareOp::Ne, _, _) => !equal,
+        (CompareOp::Lt, Value::Number(a), Value::Number(b)) => a < b,
+        (CompareOp::Le, Value::Number(a), Value::Number(b)) => a <= b,
+        (CompareOp::Gt, Value::Number(a), Value::Number(b)) => a > b,
+        (CompareOp::Ge, Value::Number(a), Value::Number(b)) => a >= b,
+        // Numbers only, and `null` is never less/greater.
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::flight::cx715;
+
+    fn eval(source: &str) -> bool {
+        Expression::parse(source).unwrap().matches(&cx715())
+    }
+
+    fn error(source: &str) -> ExpressionError {
+        Expression::parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn evaluates_against_flight() {
+        assert!(eval(r#"operator == "CPA" && route_distance > 1000 && dest.country == "Singapore""#));
+        assert!(eval("origin.icao == 'vhhh' && wake == 'heavy'"));
+        assert!(eval("delay == 20 && aircraft_type != null"));
+        assert!(!eval("route_distance >= 2000"));
+    }
+
+    #[test]
+    fn null_never_compares() {
+        let mut f = cx715();
+        f.arrival_delay = None;
+
+        let expr = |source: &str| Expression::parse(source).unwrap().matches(&f);
+        assert!(expr("delay == null"));
+        assert!(!expr("delay > -100"));
+        assert!(!expr("delay <= 100"));
+    }
+
+    #[test]
+    fn idents_match_codeshares() {
+        let mut f = cx715();
+        f.codeshares = vec!["QFA8715".into()];
+        f.codeshares_iata = vec!["QF8715".into()];
+
+        let expr = |source: &str| Expression::parse(source).unwrap().matches(&f);
+        assert!(expr("ident == 'CPA715' && iata == 'cx715'"));
+        assert!(expr("ident == 'QFA8715' && 'QF8715' == iata"));
+        assert!(expr("ident != 'QF8716'"));
+        assert!(!expr("ident != 'QF8715'"));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // true || (false && false)
+        assert!(eval("operator == 'CPA' || operator == 'SIA' && route_distance > 5000"));
+        // (true || false) && false
+        assert!(!eval("(operator == 'CPA' || operator == 'SIA') && route_distance > 5000"));
+        // `!` applies to the whole comparison.
+        assert!(eval("!operator == 'SIA' && !(route_distance < 100)"));
+        assert!(eval("!!true"));
+    }
+
+    #[test]
+    fn type_errors_have_positions() {
+        assert_eq!(
+            error("route_distance == 'far'"),
+            ExpressionError::new("Cannot compare number with text", 15),
+        );
+        assert_eq!(
+            error("operator > 3"),
+            ExpressionError::new("'>' needs numbers on both sides", 0),
+        );
+        assert_eq!(
+            error("true && route_distance"),
+            ExpressionError::new("'&&' needs true/false, but got number", 8),
+        );
+        assert_eq!(
+            error("route_distance"),
+            ExpressionError::new("Expression must be true/false, but got number", 0),
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_positions() {
+        assert_eq!(error("operatr == 'CPA'"), ExpressionError::new("Unknown field 'operatr'", 0));
+        assert_eq!(error("dest.weather == 1").position, 0);
+        assert_eq!(error("(true"), ExpressionError::new("Expected ')'", 5));
+        assert_eq!(error("true &&"), ExpressionError::new("Unexpected end of expression", 7));
+        assert_eq!(error("true true"), ExpressionError::new("Unexpected true", 5));
+        assert_eq!(error("operator == \"CPA"), ExpressionError::new("Unterminated text", 12));
+        assert_eq!(error("delay # 3"), ExpressionError::new("Unexpected character '#'", 6));
+    }
+}
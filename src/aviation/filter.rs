@@ -0,0 +1,184 @@
+/// Criteria for which flights to show. JS gives them
+/// (see `App::set_filter`), and every field is optional.
+/// Flights must meet all the given criteria, and for
+/// lists, any of the items (e.g. `"operators": ["CPA", "SQ"]`
+/// for either Cathay or Singapore Airlines).
+///
+/// Since flights are filtered when drawn (every frame),
+/// changing filters does not require fetching again.
+///
+/// For criteria not covered by the fields, `expression`
+/// takes a filter expression (see `expression.rs`) such as
+/// `dest.country == "Singapore" && delay > 15`, which must
+/// be compiled (`FlightFilter::compile`) before matching.
+
+use serde::{Deserialize, Serialize};
+
+use crate::aviation::aircraft::WakeCategory;
+use crate::aviation::delay::DelayBucket;
+use crate::aviation::expression::{Expression, ExpressionError};
+use crate::aviation::flight::Flight;
+use crate::aviation::reference::AirportRefer;
+use crate::aviation::status::FlightStatus;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlightFilter {
+    // Flight idents (e.g. `SIA917` or `SQ917`)
+    // including codeshares (see `Flight::idents`).
+    pub idents: Option<Vec<String>>,
+    // Airline codes (either ICAO or IATA).
+    pub operators: Option<Vec<String>>,
+    // ICAO designators (e.g. `B77W`).
+    pub aircraft_types: Option<Vec<String>>,
+    // e.g. `["heavy", "super"]` for widebodies.
+    pub wake: Option<Vec<WakeCategory>>,
+    pub statuses: Option<Vec<FlightStatus>>,
+    // In statute miles (as FlightAware gives).
+    pub min_route_distance: Option<i32>,
+    pub max_route_distance: Option<i32>,
+    // Airport codes (either ICAO or IATA).
+    pub origins: Option<Vec<String>>,
+    pub destinations: Option<Vec<String>>,
+    // In minutes (negative when early). Flights
+    // with unknown delays never meet them.
+    pub min_delay: Option<i32>,
+    pub max_delay: Option<i32>,
+    pub delay_buckets: Option<Vec<DelayBucket>>,
+    pub expression: Option<String>,
+    // Parsed from `expression` (by `compile`).
+    #[serde(skip)]
+    compiled: Option<Expression>,
+}
+
+impl FlightFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == FlightFilter::default()
+    }
+
+    /// Parses `expression` (if any) so that `matches`
+    /// can evaluate it.
+    pub fn compile(&mut self) -> Result<(), ExpressionError> {
+        self.compiled = match &self.expression {
+            Some(source) => Some(Expression::parse(source)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    pub fn matches(&self, flight: &Flight) -> bool {
+        // Unknown delays meet neither `min_delay` nor `max_delay`
+        // (the same as `delay` in expressions).
+        let delay: Option<i32> = flight.delay().map(|delay| delay / 60);
+
+        any_of(&self.idents, |ident| flight.matches_ident(ident)) &&
+            any_of(&self.operators, |code| matches_operator(flight, code)) &&
+            any_of(&self.aircraft_types, |code| {
+                flight.aircraft.as_ref().is_some_and(|a| a.icao.eq_ignore_ascii_case(code))
+            }) &&
+            any_of(&self.wake, |wake| {
+                flight.aircraft.as_ref().is_some_and(|a| a.wake == *wake)
+            }) &&
+            any_of(&self.statuses, |status| flight.status == *status) &&
+            self.min_route_distance.is_none_or(|min| flight.route_distance >= min) &&
+            self.max_route_distance.is_none_or(|max| flight.route_distance <= max) &&
+            any_of(&self.origins, |code| matches_airport(&flight.orig_airport, code)) &&
+            any_of(&self.destinations, |code| matches_airport(&flight.dest_airport, code)) &&
+            self.min_delay.is_none_or(|min| delay.is_some_and(|delay| delay >= min)) &&
+            self.max_delay.is_none_or(|max| delay.is_some_and(|delay| delay <= max)) &&
+            any_of(&self.delay_buckets, |bucket| flight.delay_bucket == *bucket) &&
+            self.compiled.as_ref().is_none_or(|expr| expr.matches(flight))
+    }
+}
+
+// Not given at all means anything goes.
+fn any_of<T>(items: &Option<Vec<T>>, f: impl Fn(&T) -> bool) -> bool {
+    items.as_ref().is_none_or(|items| items.iter().any(f))
+}
+
+fn matches_operator(flight: &Flight, code: &str) -> bool {
+    flight.operator.eq_ignore_ascii_case(code) ||
+        flight.airline.as_ref().is_some_and(|a| {
+            a.icao.eq_ignore_ascii_case(code) || a.iata.eq_ignore_ascii_case(code)
+        })
+}
+
+fn matches_airport(airport: &AirportRefer, code: &str) -> bool {
+    airport.icao.eq_ignore_ascii_case(code) || airport.iata.eq_ignore_ascii_case(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::flight::cx715;
+
+    fn filter(json: &str) -> FlightFilter {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(FlightFilter::default().is_empty());
+        assert!(FlightFilter::default().matches(&cx715()));
+    }
+
+    #[test]
+    fn all_criteria_must_match() {
+        let f = cx715();
+        assert!(filter(r#"{ "operators": ["SQ", "CX"] }"#).matches(&f));
+        assert!(filter(r#"{ "wake": ["heavy", "super"], "origins": ["HKG"] }"#).matches(&f));
+        assert!(filter(r#"{ "min_route_distance": 1000, "max_route_distance": 2000 }"#).matches(&f));
+        assert!(filter(r#"{ "min_delay": 15, "destinations": ["wsss"] }"#).matches(&f));
+
+        assert!(!filter(r#"{ "operators": ["SIA"] }"#).matches(&f));
+        assert!(!filter(r#"{ "operators": ["CPA"], "min_route_distance": 2000 }"#).matches(&f));
+        assert!(!filter(r#"{ "aircraft_types": ["B77W"] }"#).matches(&f));
+        assert!(!filter(r#"{ "statuses": ["cancelled"] }"#).matches(&f));
+        assert!(!filter(r#"{ "max_delay": 10 }"#).matches(&f));
+    }
+
+    #[test]
+    fn unknown_delays_meet_no_delay_criteria() {
+        let mut f = cx715();
+        f.arrival_delay = None;
+
+        assert!(!filter(r#"{ "max_delay": 10 }"#).matches(&f));
+        assert!(!filter(r#"{ "min_delay": -5 }"#).matches(&f));
+        assert!(filter(r#"{ "operators": ["CX"] }"#).matches(&f));
+
+        let mut expr = filter(r#"{ "expression": "delay <= 10" }"#);
+        expr.compile().unwrap();
+        assert!(!expr.matches(&f));
+    }
+
+    #[test]
+    fn idents_match_codeshares() {
+        let mut f = cx715();
+        f.codeshares = vec!["QFA8715".into()];
+        f.codeshares_iata = vec!["QF8715".into()];
+
+        assert!(filter(r#"{ "idents": ["CX715"] }"#).matches(&f));
+        assert!(filter(r#"{ "idents": ["SQ1", "qf8715"] }"#).matches(&f));
+        assert!(!filter(r#"{ "idents": ["QF8716"] }"#).matches(&f));
+    }
+
+    #[test]
+    fn expression_is_combined_with_fields() {
+        let f = cx715();
+        let mut expr = filter(r#"{ "operators": ["CX"], "expression": "delay > 15 && dest.iata == 'SIN'" }"#);
+        expr.compile().unwrap();
+        assert!(expr.matches(&f));
+
+        let mut expr = filter(r#"{ "expression": "route_distance > 2000" }"#);
+        expr.compile().unwrap();
+        assert!(!expr.matches(&f));
+
+        let mut expr = filter(r#"{ "expression": "delay >" }"#);
+        assert_eq!(expr.compile().unwrap_err().position, 7);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(serde_json::from_str::<FlightFilter>(r#"{ "operator": "CPA" }"#).is_err());
+    }
+}
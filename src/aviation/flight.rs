@@ -1,34 +1,125 @@
+use chrono::{DateTime, Duration, Utc};
+use std::f64::consts::PI;
 use wasm_bindgen::prelude::JsValue;
 use web_sys::{
     // console,
     CanvasRenderingContext2d,
 };
 
+use crate::aviation::aircraft::{AircraftType, lookup_aircraft_type};
+use crate::aviation::airline::{Airline, lookup_airline};
 use crate::aviation::arrival::AirportArrival;
+use crate::aviation::delay::{DelayBucket, DelayThresholds};
+use crate::aviation::status::{FlightStatus, StatusOptions};
 use crate::aviation::reference::AirportRefer;
 use crate::constants::{
+    AIRPORT_TEXT_WIDTH_DEFAULT,
+    AIRPORT_TEXT_HEIGHT_DEFAULT,
+    FONT_COLOR,
+    FONT_FAMILY,
+    FONT_SIZE,
+    TOOLTIP_BG_COLOR,
+    TOOLTIP_LINE_SPACING,
+    TOOLTIP_MARGIN,
+    TOOLTIP_OFFSET,
+    TOOLTIP_PADDING,
+    FLIGHT_CLIMB_DESCENT_MINUTES,
     FLIGHT_LINE_COLOR,
+    FLIGHT_LINE_DASH,
     FLIGHT_LINE_WIDTH,
+    FLIGHT_MARKER_COLOR,
+    FLIGHT_MARKER_SIZE,
+    FLIGHT_PATH_STEP_KM,
+};
+use crate::dimension::geo::{
+    GeoCoord,
+    Projection,
+    great_circle_distance,
+    great_circle_points,
+    rhumb_line_points,
 };
-use crate::dimension::canvas::Canvas;
-use crate::dimension::geo::LatLngBounds;
 use crate::dimension::point::PointCoord;
-use crate::dimension::geo::get_mercator_position;
+use crate::dimension::Size;
+use crate::utils::{lerp, measure_text, norm};
 
 const DEFAULT_ACCEL: f64 = 0.01;
 const DEFAULT_DECEL: f64 = 0.96;
 
+// Beyond this progress, the flight is approaching the destination.
+const APPROACHING_PROGRESS: f64 = 0.9;
+
+/// Which line the flight is drawn along.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlightPathKind {
+    /// The shortest path on the globe.
+    #[default]
+    GreatCircle,
+    /// The path with a constant bearing.
+    Rhumb,
+}
+
+impl FlightPathKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "great_circle" => Some(FlightPathKind::GreatCircle),
+            "rhumb" => Some(FlightPathKind::Rhumb),
+            _ => None,
+        }
+    }
+}
+
+/// What flight lines are colored by.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlightColorBy {
+    /// See `DelayBucket`.
+    #[default]
+    Delay,
+    /// Brand colors of airlines (see `Airline`).
+    Airline,
+}
+
+impl FlightColorBy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "delay" => Some(FlightColorBy::Delay),
+            "airline" => Some(FlightColorBy::Airline),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Flight {
+    pub id: String,
     pub icao: String,
     pub iata: String,
     pub operator: String,
     pub flight_number: String,
+    pub codeshares: Vec<String>,
+    pub codeshares_iata: Vec<String>,
+    pub airline: Option<Airline>,
+    pub aircraft: Option<AircraftType>,
     pub orig_airport: AirportRefer,
     pub dest_airport: AirportRefer,
+    pub actual_out: DateTime<Utc>,
+    pub actual_off: DateTime<Utc>,
+    pub scheduled_on: DateTime<Utc>,
+    pub estimated_on: DateTime<Utc>,
+    pub route_distance: i32, // In statute miles
+    pub departure_delay: Option<i32>,
+    pub arrival_delay: Option<i32>,
+    pub delay_bucket: DelayBucket,
+    pub status: FlightStatus,
+    pub visible: bool,
     pub index: usize,
+    pub path_kind: FlightPathKind,
+    pub path: Vec<GeoCoord>,
+    pub path_points: Vec<PointCoord>,
     pub orig: PointCoord,
     pub dest: PointCoord,
+    pub pos: PointCoord,
+    pub progress: f64,
+    pub landed: bool,
     pub velocity: PointCoord,
     pub vmax: f64,
     pub speed: f64,
@@ -42,16 +133,41 @@ pub struct Flight {
 
 impl Flight {
     pub fn new(arrival: AirportArrival) -> Self {
-        Flight {
+        let airline: Option<Airline> = lookup_airline(&arrival.operator);
+
+        let mut flight = Flight {
+            id: arrival.id,
             icao: arrival.icao,
             iata: arrival.iata,
             operator: arrival.operator,
             flight_number: arrival.flight_number,
+            codeshares: arrival.codeshares,
+            codeshares_iata: arrival.codeshares_iata,
+            airline,
+            aircraft: arrival.aircraft_type
+                .as_deref()
+                .and_then(lookup_aircraft_type),
             orig_airport: arrival.orig_airport,
             dest_airport: arrival.dest_airport,
+            actual_out: arrival.actual_out,
+            actual_off: arrival.actual_off,
+            scheduled_on: arrival.scheduled_on,
+            estimated_on: arrival.estimated_on,
+            route_distance: arrival.route_distance,
+            departure_delay: arrival.departure_delay,
+            arrival_delay: arrival.arrival_delay,
+            delay_bucket: DelayBucket::default(),
+            status: arrival.status,
+            visible: true,
             index: 0_usize,
+            path_kind: FlightPathKind::default(),
+            path: vec![],
+            path_points: vec![],
             orig: PointCoord::default(),
             dest: PointCoord::default(),
+            pos: PointCoord::default(),
+            progress: 0_f64,
+            landed: false,
             velocity: PointCoord::default(),
             vmax: 1_f64,
             speed: 0_f64,
@@ -61,38 +177,648 @@ impl Flight {
             dest_index: 0_u8,
             approaching: false,
             holding: false,
+        };
+        flight.set_path();
+
+        // Some records come without sensible arrival times.
+        if flight.estimated_on <= flight.actual_off {
+            if let Some(eta) = flight.cruise_eta() {
+                flight.estimated_on = eta;
+            }
         }
+
+        flight
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn update(
-        &mut self,
-        canvas: &Canvas,
-        bounds: &LatLngBounds,
-    ) {
-        self.orig = get_mercator_position(
-            &canvas.size(),
-            bounds,
+    /// How long it takes from `actual_off` to landing
+    /// when flying at the typical cruise speed
+    /// of the aircraft type (when known).
+    pub fn cruise_duration(&self) -> Option<Duration> {
+        let speed: f64 = self.aircraft.as_ref()?.cruise_speed;
+        if speed <= 0.0 {
+            return None;
+        }
+
+        let distance: f64 = great_circle_distance(
             &self.orig_airport.coord,
+            &self.dest_airport.coord,
         );
+        let minutes: f64 = distance / speed * 60.0;
 
-        self.dest = get_mercator_position(
-            &canvas.size(),
-            bounds,
-            &self.dest_airport.coord,
+        Some(
+            Duration::minutes(FLIGHT_CLIMB_DESCENT_MINUTES) +
+                Duration::seconds((minutes * 60.0) as i64)
+        )
+    }
+
+    pub fn cruise_eta(&self) -> Option<DateTime<Utc>> {
+        self.cruise_duration().map(|d| self.actual_off + d)
+    }
+
+    pub fn line_color(&self, color_by: FlightColorBy) -> &str {
+        match color_by {
+            FlightColorBy::Delay => self.delay_bucket.color(),
+            FlightColorBy::Airline => self.airline
+                .as_ref()
+                .map_or(FLIGHT_LINE_COLOR, |a| a.color.as_str()),
+        }
+    }
+
+    /// Bigger aircraft get bigger markers.
+    pub fn marker_size(&self) -> f64 {
+        let scale: f64 = self.aircraft
+            .as_ref()
+            .map_or(1.0, |a| a.wake.marker_scale());
+        FLIGHT_MARKER_SIZE * scale
+    }
+
+    /// Switch between great-circle and rhumb line.
+    /// Positions are projected again on the next `update()`.
+    pub fn set_path_kind(&mut self, kind: FlightPathKind) {
+        self.path_kind = kind;
+        self.set_path();
+    }
+
+    /// The operating ident first, then the marketing ones
+    /// (both ICAO and IATA).
+    pub fn idents(&self) -> Vec<&str> {
+        std::iter::once(&self.icao)
+            .chain(std::iter::once(&self.iata))
+            .chain(self.codeshares.iter())
+            .chain(self.codeshares_iata.iter())
+            .map(String::as_str)
+            .filter(|ident| !ident.is_empty())
+            .collect()
+    }
+
+    /// Whether `ident` (case insensitive) is any of `idents()`.
+    pub fn matches_ident(&self, ident: &str) -> bool {
+        self.idents().iter().any(|i| i.eq_ignore_ascii_case(ident))
+    }
+
+    /// Delay (in seconds) for coloring. We care
+    /// for arrivals the most, but when not known yet,
+    /// the departure delay tells a lot.
+    pub fn delay(&self) -> Option<i32> {
+        self.arrival_delay.or(self.departure_delay)
+    }
+
+    /// Called whenever thresholds change.
+    pub fn set_delay_bucket(&mut self, thresholds: &DelayThresholds) {
+        self.delay_bucket = thresholds.bucket(self.delay());
+    }
+
+    // Samples geo-coordinates along the route
+    // (roughly every `FLIGHT_PATH_STEP_KM`).
+    fn set_path(&mut self) {
+        let from: &GeoCoord = &self.orig_airport.coord;
+        let to: &GeoCoord = &self.dest_airport.coord;
+        let segments: usize =
+            (great_circle_distance(from, to) / FLIGHT_PATH_STEP_KM).ceil() as usize;
+
+        self.path = match self.path_kind {
+            FlightPathKind::GreatCircle => great_circle_points(from, to, segments),
+            FlightPathKind::Rhumb => rhumb_line_points(from, to, segments),
+        };
+    }
+
+    pub fn update(&mut self, projection: &dyn Projection) {
+        // Projected as a whole, so that flights crossing
+        // the antimeridian are not torn apart.
+        self.path_points = projection.project_path(&self.path);
+
+        // Ends of the path rather than the airports' own
+        // positions, since we want the same world copy.
+        if let (Some(first), Some(last)) = (self.path_points.first(), self.path_points.last()) {
+            self.orig = first.clone();
+            self.dest = last.clone();
+        }
+    }
+
+    /// How far the flight is between `actual_off` (0.0)
+    /// and `estimated_on` (1.0) at `now`.
+    /// Flights not yet departed stay at 0.0,
+    /// and landed flights are `None`.
+    pub fn progress_at(&self, now: DateTime<Utc>) -> Option<f64> {
+        if now >= self.estimated_on {
+            return None;
+        }
+
+        if now <= self.actual_off {
+            return Some(0_f64);
+        }
+
+        Some(norm(
+            now.timestamp_millis() as f64,
+            self.actual_off.timestamp_millis() as f64,
+            self.estimated_on.timestamp_millis() as f64,
+        ))
+    }
+
+    // Updating the current position against `now`
+    // (given from `Clock`). Unlike `update()`,
+    // this is called every frame in `Manager::update()`.
+    pub fn advance(&mut self, now: DateTime<Utc>) {
+        // Cancelled flights never leave, and disappear
+        // when they would have arrived.
+        if self.status == FlightStatus::Cancelled {
+            self.landed = now >= self.estimated_on;
+            self.progress = 0_f64;
+            self.holding = !self.landed;
+            self.approaching = false;
+            self.set_position();
+            return;
+        }
+
+        match self.progress_at(now) {
+            Some(progress) => {
+                self.landed = false;
+                self.progress = progress;
+                self.holding = now <= self.actual_off;
+                self.approaching = progress >= APPROACHING_PROGRESS;
+            },
+            None => {
+                self.landed = true;
+                self.progress = 1_f64;
+                self.holding = false;
+                self.approaching = false;
+            },
+        }
+
+        if !self.status.is_disrupted() {
+            self.status = if self.landed {
+                FlightStatus::Landed
+            } else if !self.holding {
+                FlightStatus::Airborne
+            } else if now >= self.actual_out {
+                FlightStatus::Taxiing
+            } else {
+                FlightStatus::Scheduled
+            };
+        }
+
+        self.set_position();
+    }
+
+    // Finds where `progress` falls in `path_points`.
+    // Points are sampled evenly, so each segment
+    // shares the same amount of `progress`.
+    fn set_position(&mut self) {
+        let points: &[PointCoord] = &self.path_points;
+
+        if points.len() < 2 {
+            self.pos = points.first().cloned().unwrap_or_default();
+            return;
+        }
+
+        let segments: usize = points.len() - 1;
+        let t: f64 = self.progress * segments as f64;
+        let i: usize = (t.floor() as usize).min(segments - 1);
+        let (a, b) = (&points[i], &points[i + 1]);
+
+        self.pos = PointCoord::new(
+            lerp(t - i as f64, a.x, b.x),
+            lerp(t - i as f64, a.y, b.y),
         );
+        self.angle = (b.y - a.y).atan2(b.x - a.x);
+    }
+
+    /// How far `point` is from the marker drawn on the canvas
+    /// (0.0 when right on it). Landed flights are not drawn,
+    /// and they give `None`.
+    pub fn marker_distance(&self, point: &PointCoord) -> Option<f64> {
+        if self.landed || !self.visible || self.status == FlightStatus::Cancelled {
+            return None;
+        }
+        Some((point.distance(&self.pos) - self.marker_size()).max(0.0))
+    }
+
+    /// Same as `marker_distance()`, but for the path.
+    pub fn path_distance(&self, point: &PointCoord) -> Option<f64> {
+        if self.landed || !self.visible || self.path_points.len() < 2 {
+            return None;
+        }
+
+        self.path_points
+            .windows(2)
+            .map(|w| point.distance_to_segment(&w[0], &w[1]))
+            .reduce(f64::min)
     }
 
-    pub fn draw(&self, ctx: &CanvasRenderingContext2d) {
+    pub fn draw(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        options: &StatusOptions,
+        color_by: FlightColorBy,
+    ) {
+        if self.landed || !self.visible {
+            return;
+        }
+
+        let cancelled: bool = self.status == FlightStatus::Cancelled;
+
         ctx.save();
         ctx.set_stroke_style(
-            &JsValue::from_str(FLIGHT_LINE_COLOR)
+            &JsValue::from_str(self.line_color(color_by))
         );
         ctx.set_line_width(FLIGHT_LINE_WIDTH);
+        if cancelled && options.dash_cancelled {
+            let dash = js_sys::Array::of2(
+                &JsValue::from_f64(FLIGHT_LINE_DASH),
+                &JsValue::from_f64(FLIGHT_LINE_DASH),
+            );
+            ctx.set_line_dash(&dash).unwrap_or(());
+        }
         ctx.begin_path();
-        ctx.move_to(self.orig.x, self.orig.y);
-        ctx.line_to(self.dest.x, self.dest.y);
+        self.path_points.iter().enumerate().for_each(|(i, p)| {
+            if i == 0 {
+                ctx.move_to(p.x, p.y);
+            } else {
+                ctx.line_to(p.x, p.y);
+            }
+        });
         ctx.stroke();
         ctx.restore();
+
+        // Nothing is flying for cancelled flights.
+        if !cancelled {
+            self.draw_marker(ctx);
+        }
+    }
+
+    /// What the tooltip says (one line each).
+    // Times are in local time of the origin (departure)
+    // and the destination (arrivals).
+    pub fn tooltip_lines(&self) -> Vec<String> {
+        let departure = |t: &DateTime<Utc>| self.orig_airport.details.local_time(t);
+        let arrival = |t: &DateTime<Utc>| self.dest_airport.details.local_time(t);
+
+        let operator: &str = self.airline
+            .as_ref()
+            .map_or(self.operator.as_str(), |a| a.name.as_str());
+
+        let title: String = match &self.aircraft {
+            Some(aircraft) => format!(
+                "{} ({}) · {} {}",
+                self.icao,
+                operator,
+                aircraft.manufacturer,
+                aircraft.model,
+            ),
+            None => format!("{} ({})", self.icao, operator),
+        };
+
+        let mut lines: Vec<String> = vec![
+            title,
+            format!("{} → {}", self.orig_airport.name, self.dest_airport.name),
+            format!("Departed {}", departure(&self.actual_off)),
+            format!(
+                "Scheduled {} / Estimated {}",
+                arrival(&self.scheduled_on),
+                arrival(&self.estimated_on),
+            ),
+            format!(
+                "{} · Progress {}%",
+                self.status.label(),
+                (self.progress * 100.0).round(),
+            ),
+        ];
+
+        if !self.codeshares.is_empty() {
+            lines.insert(1, format!("Also {}", self.codeshares.join(", ")));
+        }
+
+        lines
+    }
+
+    // Called in `Manager::draw()` for the selected flight
+    // (after all the flights so that it comes on top).
+    // Text sizes are measured every time since `progress`
+    // keeps changing.
+    pub fn draw_tooltip(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        canvas: &Size,
+    ) {
+        if self.landed {
+            return;
+        }
+
+        let lines: Vec<String> = self.tooltip_lines();
+
+        ctx.save();
+        ctx.set_font(
+            format!("{}px {}", FONT_SIZE, FONT_FAMILY).as_str()
+        );
+
+        let sizes: Vec<(f64, f64)> = lines.iter()
+            .map(|line| measure_text(
+                ctx,
+                line,
+                (
+                    AIRPORT_TEXT_WIDTH_DEFAULT,
+                    AIRPORT_TEXT_HEIGHT_DEFAULT
+                ),
+            ))
+            .collect();
+
+        // Lines share the tallest height so that they align.
+        let line_height: f64 = sizes.iter()
+            .map(|(_, h)| *h)
+            .fold(0_f64, f64::max);
+
+        let box_size = Size::new(
+            sizes.iter().map(|(w, _)| *w).fold(0_f64, f64::max) +
+                TOOLTIP_PADDING * 2.0,
+            (line_height + TOOLTIP_LINE_SPACING) * lines.len() as f64 -
+                TOOLTIP_LINE_SPACING + TOOLTIP_PADDING * 2.0,
+        );
+
+        let pos: PointCoord = tooltip_position(&self.pos, &box_size, canvas);
+
+        ctx.set_fill_style_str(TOOLTIP_BG_COLOR);
+        ctx.fill_rect(pos.x, pos.y, box_size.width, box_size.height);
+
+        ctx.set_fill_style_str(FONT_COLOR);
+        lines.iter().enumerate().for_each(|(i, line)| {
+            ctx.fill_text(
+                line,
+                pos.x + TOOLTIP_PADDING,
+                pos.y + TOOLTIP_PADDING +
+                    (line_height + TOOLTIP_LINE_SPACING) * i as f64 +
+                    line_height,
+            ).unwrap_or(());
+        });
+        ctx.restore();
+    }
+
+    // A small triangle pointing toward the destination.
+    fn draw_marker(&self, ctx: &CanvasRenderingContext2d) {
+        let size: f64 = self.marker_size();
+        let wing: f64 = PI * 0.8;
+
+        ctx.save();
+        ctx.set_fill_style_str(FLIGHT_MARKER_COLOR);
+        ctx.begin_path();
+        ctx.move_to(
+            self.pos.x + size * self.angle.cos(),
+            self.pos.y + size * self.angle.sin(),
+        );
+        ctx.line_to(
+            self.pos.x + size * (self.angle + wing).cos(),
+            self.pos.y + size * (self.angle + wing).sin(),
+        );
+        ctx.line_to(
+            self.pos.x + size * (self.angle - wing).cos(),
+            self.pos.y + size * (self.angle - wing).sin(),
+        );
+        ctx.close_path();
+        ctx.fill();
+        ctx.restore();
+    }
+}
+
+/// Where to put the tooltip box (its top-left corner).
+/// We want it below-right of `anchor`, but it flips
+/// to the other side when it does not fit, and finally,
+/// it is kept inside the canvas just like the way
+/// `Airport::update` does for airport names.
+fn tooltip_position(
+    anchor: &PointCoord,
+    box_size: &Size,
+    canvas: &Size,
+) -> PointCoord {
+    let mut x: f64 = anchor.x + TOOLTIP_OFFSET;
+    let mut y: f64 = anchor.y + TOOLTIP_OFFSET;
+
+    if (x + box_size.width) > canvas.width - TOOLTIP_MARGIN {
+        x = anchor.x - TOOLTIP_OFFSET - box_size.width;
+    }
+
+    if (y + box_size.height) > canvas.height - TOOLTIP_MARGIN {
+        y = anchor.y - TOOLTIP_OFFSET - box_size.height;
+    }
+
+    let x_max: f64 = canvas.width - box_size.width - TOOLTIP_MARGIN;
+    let y_max: f64 = canvas.height - box_size.height - TOOLTIP_MARGIN;
+
+    PointCoord::new(
+        x.min(x_max).max(TOOLTIP_MARGIN),
+        y.min(y_max).max(TOOLTIP_MARGIN),
+    )
+}
+
+/// Cathay Pacific 715 (Hong Kong to Singapore,
+/// 20 minutes late) shared by tests of filters.
+#[cfg(test)]
+pub(crate) fn cx715() -> Flight {
+    let mut flight = Flight::new(AirportArrival {
+        icao: "CPA715".into(),
+        iata: "CX715".into(),
+        operator: "CPA".into(),
+        route_distance: 1600,
+        arrival_delay: Some(20 * 60),
+        ..AirportArrival::default()
+    });
+    flight.airline = lookup_airline("CPA");
+    flight.aircraft = lookup_aircraft_type("A333");
+    flight.orig_airport.icao = "VHHH".into();
+    flight.orig_airport.iata = "HKG".into();
+    flight.dest_airport.icao = "WSSS".into();
+    flight.dest_airport.iata = "SIN".into();
+    flight.dest_airport.country = "Singapore".into();
+    flight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn flight() -> Flight {
+        let off = Utc.with_ymd_and_hms(2022, 8, 24, 6, 25, 0).unwrap();
+        let arrival = AirportArrival {
+            actual_off: off,
+            estimated_on: off + Duration::hours(2),
+            ..AirportArrival::default()
+        };
+
+        let mut flight = Flight::new(arrival);
+        flight.path_points = vec![
+            PointCoord::new(0.0, 0.0),
+            PointCoord::new(40.0, 30.0),
+            PointCoord::new(100.0, 50.0),
+        ];
+        flight
+    }
+
+    #[test]
+    fn advance_interpolates_between_endpoints() {
+        let mut f = flight();
+        f.advance(f.actual_off + Duration::minutes(30));
+        assert_eq!(f.progress, 0.25);
+        assert_eq!((f.pos.x, f.pos.y), (20.0, 15.0));
+        assert!(!f.holding && !f.landed);
+
+        f.advance(f.actual_off + Duration::minutes(90));
+        assert_eq!((f.pos.x, f.pos.y), (70.0, 40.0));
+    }
+
+    #[test]
+    fn advance_holds_at_origin_before_departure() {
+        let mut f = flight();
+        f.advance(f.actual_off - Duration::minutes(10));
+        assert_eq!((f.pos.x, f.pos.y), (0.0, 0.0));
+        assert!(f.holding);
+    }
+
+    #[test]
+    fn path_kind_resamples_path() {
+        let mut f = flight();
+        f.orig_airport.coord = GeoCoord::new(1.356, 103.987);
+        f.dest_airport.coord = GeoCoord::new(25.08, 121.232);
+        f.set_path_kind(FlightPathKind::Rhumb);
+        assert_eq!(f.path.len(), 34);
+        assert_eq!(f.path_kind, FlightPathKind::Rhumb);
+    }
+
+    #[test]
+    fn advance_lands_after_arrival() {
+        let mut f = flight();
+        f.advance(f.estimated_on + Duration::minutes(1));
+        assert!(f.landed);
+    }
+
+    #[test]
+    fn delay_bucket_prefers_arrival_delay() {
+        let mut f = flight();
+        let thresholds = DelayThresholds::default();
+
+        f.departure_delay = Some(1200);
+        f.set_delay_bucket(&thresholds);
+        assert_eq!(f.delay_bucket, DelayBucket::Minor);
+
+        f.arrival_delay = Some(-600);
+        f.set_delay_bucket(&thresholds);
+        assert_eq!(f.delay_bucket, DelayBucket::Early);
+    }
+
+    #[test]
+    fn tooltip_lines_show_times_and_progress() {
+        let mut f = flight();
+        f.icao = "SIA917".into();
+        f.operator = "SIA".into();
+        f.airline = lookup_airline("SIA");
+        f.orig_airport.name = "Ninoy Aquino International".into();
+        f.dest_airport.name = "Singapore Changi".into();
+        f.scheduled_on = f.estimated_on - Duration::minutes(5);
+        f.advance(f.actual_off + Duration::minutes(30));
+
+        assert_eq!(f.tooltip_lines(), vec![
+            "SIA917 (Singapore Airlines)",
+            "Ninoy Aquino International → Singapore Changi",
+            "Departed 06:25 UTC",
+            "Scheduled 08:20 UTC / Estimated 08:25 UTC",
+            "Airborne · Progress 25%",
+        ]);
+
+        f.orig_airport.details.tz = Some("Asia/Manila".into());
+        f.dest_airport.details.tz = Some("Asia/Singapore".into());
+        assert_eq!(f.tooltip_lines()[2..4], [
+            "Departed 14:25 PST",
+            "Scheduled 16:20 +08 / Estimated 16:25 +08",
+        ]);
+    }
+
+    #[test]
+    fn tooltip_stays_inside_canvas() {
+        let canvas = Size::new(800.0, 600.0);
+        let box_size = Size::new(200.0, 100.0);
+
+        let p = tooltip_position(&PointCoord::new(100.0, 100.0), &box_size, &canvas);
+        assert_eq!((p.x, p.y), (114.0, 114.0));
+
+        // Flips to the left and above near the bottom-right corner.
+        let p = tooltip_position(&PointCoord::new(700.0, 550.0), &box_size, &canvas);
+        assert_eq!((p.x, p.y), (486.0, 436.0));
+
+        // Pushed back in near the top-left corner.
+        let p = tooltip_position(&PointCoord::new(-50.0, -50.0), &box_size, &canvas);
+        assert_eq!((p.x, p.y), (20.0, 20.0));
+    }
+
+    #[test]
+    fn status_follows_clock_unless_disrupted() {
+        let mut f = flight();
+        f.actual_out = f.actual_off - Duration::minutes(15);
+
+        f.advance(f.actual_off - Duration::minutes(30));
+        assert_eq!(f.status, FlightStatus::Scheduled);
+        f.advance(f.actual_off - Duration::minutes(5));
+        assert_eq!(f.status, FlightStatus::Taxiing);
+        f.advance(f.actual_off + Duration::minutes(5));
+        assert_eq!(f.status, FlightStatus::Airborne);
+        f.advance(f.estimated_on + Duration::minutes(5));
+        assert_eq!(f.status, FlightStatus::Landed);
+
+        let mut f = flight();
+        f.status = FlightStatus::Cancelled;
+        f.advance(f.actual_off + Duration::minutes(30));
+        assert_eq!(f.status, FlightStatus::Cancelled);
+        assert_eq!((f.pos.x, f.pos.y), (0.0, 0.0));
+        assert!(f.holding && !f.landed);
+    }
+
+    #[test]
+    fn matches_any_codeshare_ident() {
+        let mut f = flight();
+        f.icao = "SIA917".into();
+        f.iata = "SQ917".into();
+        f.codeshares = vec!["UAL9416".into()];
+        f.codeshares_iata = vec!["UA9416".into()];
+
+        assert_eq!(f.idents(), vec!["SIA917", "SQ917", "UAL9416", "UA9416"]);
+        assert!(f.matches_ident("ua9416"));
+        assert!(!f.matches_ident("UA9417"));
+        assert_eq!(f.tooltip_lines()[1], "Also UAL9416");
+    }
+
+    #[test]
+    fn aircraft_type_sizes_marker_and_estimates_arrival() {
+        let off = Utc.with_ymd_and_hms(2022, 8, 24, 6, 25, 0).unwrap();
+        let mut orig_airport = AirportRefer::default();
+        let mut dest_airport = AirportRefer::default();
+        orig_airport.coord = GeoCoord::new(1.356, 103.987);
+        dest_airport.coord = GeoCoord::new(25.08, 121.232);
+
+        // No arrival time (same as the departure).
+        let f = Flight::new(AirportArrival {
+            actual_off: off,
+            estimated_on: off,
+            aircraft_type: Some("B77W".into()),
+            orig_airport,
+            dest_airport,
+            ..AirportArrival::default()
+        });
+
+        assert_eq!(f.aircraft.as_ref().unwrap().model, "777-300ER");
+        assert_eq!(f.marker_size(), FLIGHT_MARKER_SIZE * 1.3);
+
+        // 3,230 km at 905 km/h (214 min) + 20 min.
+        let minutes = (f.estimated_on - off).num_minutes();
+        assert!((232..=236).contains(&minutes), "{}", minutes);
+        assert_eq!(flight().marker_size(), FLIGHT_MARKER_SIZE);
+    }
+
+    #[test]
+    fn colored_by_airline_or_delay() {
+        let mut f = flight();
+        f.arrival_delay = Some(7200);
+        f.set_delay_bucket(&DelayThresholds::default());
+        assert_eq!(f.line_color(FlightColorBy::Delay), DelayBucket::Major.color());
+        assert_eq!(f.line_color(FlightColorBy::Airline), FLIGHT_LINE_COLOR);
+
+        f.airline = lookup_airline("SQ");
+        assert_eq!(f.line_color(FlightColorBy::Airline), "#f2a900");
     }
 }
@@ -4,9 +4,28 @@
 /// (1) For airports fed by JS that we want them plotted on Google map, or
 /// (2) For arrival/departure information fetched from FlightAware API.
 /// (3) For airport database so that allows us to validate airports.
+/// (4) For sources from which arrival/departure information comes.
+/// (5) For hit-testing flights and airports drawn on the canvas.
+/// (6) For bucketing flights by their delays.
+/// (7) For flight status (including cancelled and diverted).
+/// (8) For aircraft type database (size and speed of aircraft).
+/// (9) For airline database (names, brand colors and idents).
+/// (10) For filtering flights to show (also with expressions).
+/// (11) For searching airports by name, city, country or codes.
+/// (12) For summary numbers of flights (per airport, route and operator).
 
+pub mod aircraft;
+pub mod airline;
 #[allow(clippy::module_inception)]
 pub mod airport;
 pub mod arrival;
+pub mod delay;
+pub mod expression;
+pub mod filter;
 pub mod flight;
+pub mod pick;
 pub mod reference;
+pub mod search;
+pub mod source;
+pub mod stats;
+pub mod status;
@@ -0,0 +1,198 @@
+/// Hit-testing for what is drawn on the canvas.
+/// Given a point (in canvas pixels), we look for
+/// the flight or the airport nearest to it, using
+/// the screen positions already calculated
+/// in `Flight::update` and `Airport::update`.
+///
+/// Since every flight path ends at an airport dot,
+/// clicking a dot would also hit the paths.
+/// So, candidates are tried in the following order,
+/// and the nearest one within the tolerance wins:
+///
+/// (1) Flight markers,
+/// (2) Airport dots and labels, and
+/// (3) Flight paths.
+
+use serde::Serialize;
+
+use crate::aviation::airport::Airport;
+use crate::aviation::flight::Flight;
+use crate::aviation::status::FlightStatus;
+use crate::dimension::point::PointCoord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickKind {
+    Flight,
+    Airport,
+}
+
+/// What JS receives from `App::pick`.
+/// Flight-only fields are `null` for airports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PickResult {
+    pub kind: PickKind,
+    pub id: String, // `fa_flight_id` or airport ICAO
+    pub label: String, // `SIA917` or `Singapore Changi (Singapore)`
+    pub distance: f64, // In canvas pixels
+    pub x: f64, // Marker or dot position
+    pub y: f64,
+    pub operator: Option<String>,
+    pub origin: Option<String>, // ICAO
+    pub destination: Option<String>, // ICAO
+    pub progress: Option<f64>,
+    pub status: Option<FlightStatus>,
+    pub codeshares: Vec<String>, // Marketing idents (ICAO)
+    // In the destination's local time (e.g. `16:25 +08`).
+    pub scheduled_arrival: Option<String>,
+    pub estimated_arrival: Option<String>,
+}
+
+impl PickResult {
+    pub fn from_flight(flight: &Flight, distance: f64) -> Self {
+        PickResult {
+            kind: PickKind::Flight,
+            id: flight.id.clone(),
+            label: flight.icao.clone(),
+            distance,
+            x: flight.pos.x,
+            y: flight.pos.y,
+            operator: Some(flight.operator.clone()),
+            origin: Some(flight.orig_airport.icao.clone()),
+            destination: Some(flight.dest_airport.icao.clone()),
+            progress: Some(flight.progress),
+            status: Some(flight.status),
+            codeshares: flight.codeshares.clone(),
+            scheduled_arrival: Some(flight.dest_airport.details.local_time(&flight.scheduled_on)),
+            estimated_arrival: Some(flight.dest_airport.details.local_time(&flight.estimated_on)),
+        }
+    }
+
+    pub fn from_airport(airport: &Airport, distance: f64) -> Self {
+        PickResult {
+            kind: PickKind::Airport,
+            id: airport.icao.clone(),
+            label: airport.get_airport_text(),
+            distance,
+            x: airport.pos.x,
+            y: airport.pos.y,
+            operator: None,
+            origin: None,
+            destination: None,
+            progress: None,
+            status: None,
+            codeshares: vec![],
+            scheduled_arrival: None,
+            estimated_arrival: None,
+        }
+    }
+}
+
+/// Nearest flight or airport within `tolerance` pixels
+/// from `point` (see the top of this file for the order).
+pub fn pick(
+    flights: &[Flight],
+    airports: &[Airport],
+    point: &PointCoord,
+    tolerance: f64,
+) -> Option<PickResult> {
+    let markers = flights.iter()
+        .filter_map(|f| {
+            f.marker_distance(point).map(|d| PickResult::from_flight(f, d))
+        });
+
+    let dots = airports.iter()
+        .map(|a| PickResult::from_airport(a, a.hit_distance(point)));
+
+    let paths = flights.iter()
+        .filter_map(|f| {
+            f.path_distance(point).map(|d| PickResult::from_flight(f, d))
+        });
+
+    nearest(markers, tolerance)
+        .or_else(|| nearest(dots, tolerance))
+        .or_else(|| nearest(paths, tolerance))
+}
+
+fn nearest(
+    candidates: impl Iterator<Item = PickResult>,
+    tolerance: f64,
+) -> Option<PickResult> {
+    candidates
+        .filter(|c| c.distance <= tolerance)
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::airport::TargetAirportRawData;
+    use crate::aviation::arrival::AirportArrival;
+
+    fn flight(id: &str, points: Vec<PointCoord>, pos: PointCoord) -> Flight {
+        let mut flight = Flight::new(AirportArrival {
+            id: id.into(),
+            icao: id.into(),
+            ..AirportArrival::default()
+        });
+        flight.path_points = points;
+        flight.pos = pos;
+        flight
+    }
+
+    fn airport(icao: &str, pos: PointCoord) -> Airport {
+        let mut airport = Airport::new(TargetAirportRawData {
+            icao: icao.into(),
+            iata: "".into(),
+            name: icao.into(),
+            ..TargetAirportRawData::default()
+        });
+        airport.text_width = 60.0;
+        airport.text_height = 10.0;
+        airport.text_pos = PointCoord::new(pos.x - 48.0, pos.y - 10.0);
+        airport.pos = pos;
+        airport
+    }
+
+    #[test]
+    fn picks_marker_then_airport_then_path() {
+        let flights = vec![
+            flight(
+                "F1",
+                vec![PointCoord::new(0.0, 0.0), PointCoord::new(200.0, 0.0)],
+                PointCoord::new(100.0, 0.0),
+            ),
+        ];
+        let airports = vec![airport("WSSS", PointCoord::new(200.0, 0.0))];
+
+        let hit = pick(&flights, &airports, &PointCoord::new(104.0, 12.0), 6.0).unwrap();
+        assert_eq!(hit.kind, PickKind::Flight);
+        assert_eq!(hit.id, "F1");
+
+        // Right on the dot (which the path also ends at).
+        let hit = pick(&flights, &airports, &PointCoord::new(201.0, 0.0), 6.0).unwrap();
+        assert_eq!(hit.kind, PickKind::Airport);
+        assert_eq!(hit.id, "WSSS");
+
+        // Label box spans x: 152..212, y: -20..-10.
+        let hit = pick(&flights, &airports, &PointCoord::new(160.0, -15.0), 2.0).unwrap();
+        assert_eq!(hit.kind, PickKind::Airport);
+
+        let hit = pick(&flights, &airports, &PointCoord::new(40.0, 5.0), 6.0).unwrap();
+        assert_eq!(hit.kind, PickKind::Flight);
+        assert_eq!(hit.distance, 5.0);
+
+        assert_eq!(pick(&flights, &airports, &PointCoord::new(40.0, 50.0), 6.0), None);
+    }
+
+    #[test]
+    fn landed_flights_are_not_picked() {
+        let mut f = flight(
+            "F1",
+            vec![PointCoord::new(0.0, 0.0), PointCoord::new(200.0, 0.0)],
+            PointCoord::new(100.0, 0.0),
+        );
+        f.landed = true;
+        assert_eq!(pick(&[f], &[], &PointCoord::new(100.0, 0.0), 6.0), None);
+    }
+}
@@ -2,12 +2,30 @@
 /// we want to check if they are valid airports.
 /// The file provides a  lookup table for validation,
 /// and associated structs.
+///
+/// The lookup table (`AirportDatabase`) is usually the one
+/// bundled (`json/airports.json`), but JS may give us
+/// an OurAirports `airports.csv` (https://ourairports.com/data/)
+/// to use either instead of, or on top of the bundled one
+/// (see `App::load_airport_database`). Lookups always go
+/// to the _active_ database.
+///
+/// The bundled one has `iso_country` for all the airports,
+/// and `tz` for those whose time zone could be told
+/// (not for some in countries having more than one).
+/// It has no `elevation`, `type`, `iso_region` nor
+/// `scheduled_service`, which come only from OurAirports.
 
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 // use web_sys::console;
 
-use crate::dimension::geo::{GeoCoordTrait, GeoCoord};
+use crate::dimension::geo::{GeoCoordTrait, GeoCoord, LatLngBounds};
+use crate::dimension::spatial::{Neighbor, SpatialIndex};
+use crate::error::FlightPackError;
 use crate::utils::get_json;
 
 /// Airport information stored in our Airport Database.
@@ -19,6 +37,96 @@ pub struct AirportRefer {
     pub city: String,
     pub country: String,
     pub coord: GeoCoord,
+    #[serde(flatten)]
+    pub details: AirportDetails,
+}
+
+/// What we may or may not know about airports (e.g. the bundled
+/// database has no elevations, and OurAirports has no time zones).
+/// Flattened into `AirportRefer` and `TargetAirportRawData`,
+/// and left out of JSON when unknown.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirportDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tz: Option<String>, // IANA (e.g. `Asia/Singapore`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<i32>, // In feet
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<AirportKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iso_country: Option<String>, // ISO 3166-1 (e.g. `SG`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iso_region: Option<String>, // ISO 3166-2 (e.g. `SG-04`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_service: Option<bool>, // Whether airlines fly there
+}
+
+impl AirportDetails {
+    /// `t` in the airport's local time (e.g. `16:25 +08`),
+    /// or in UTC when the time zone is unknown.
+    pub fn local_time(&self, t: &DateTime<Utc>) -> String {
+        format_local_time(t, self.tz.as_deref())
+    }
+
+    /// Takes what we do not know from `other`
+    /// (usually, the same airport in another database).
+    pub fn fill_missing_from(&mut self, other: &AirportDetails) {
+        if self.tz.is_none() {
+            self.tz = other.tz.clone();
+        }
+        if self.elevation.is_none() {
+            self.elevation = other.elevation;
+        }
+        if self.kind.is_none() {
+            self.kind = other.kind;
+        }
+        if self.iso_country.is_none() {
+            self.iso_country = other.iso_country.clone();
+        }
+        if self.iso_region.is_none() {
+            self.iso_region = other.iso_region.clone();
+        }
+        if self.scheduled_service.is_none() {
+            self.scheduled_service = other.scheduled_service;
+        }
+    }
+}
+
+/// `t` in the time zone `tz` (IANA) such as `16:25 +08`
+/// or `01:25 PDT`, or `08:25 UTC` when `tz` is unknown.
+pub fn format_local_time(t: &DateTime<Utc>, tz: Option<&str>) -> String {
+    match tz.and_then(|tz| tz.parse::<Tz>().ok()) {
+        Some(tz) => t.with_timezone(&tz).format("%H:%M %Z").to_string(),
+        None => t.format("%H:%M UTC").to_string(),
+    }
+}
+
+/// Types of airports (as OurAirports calls them).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AirportKind {
+    LargeAirport,
+    MediumAirport,
+    SmallAirport,
+    Heliport,
+    SeaplaneBase,
+    BalloonPort,
+    Closed,
+}
+
+impl AirportKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "large_airport" | "large" => Some(AirportKind::LargeAirport),
+            "medium_airport" | "medium" => Some(AirportKind::MediumAirport),
+            "small_airport" | "small" => Some(AirportKind::SmallAirport),
+            "heliport" => Some(AirportKind::Heliport),
+            "seaplane_base" => Some(AirportKind::SeaplaneBase),
+            "balloonport" | "balloon_port" => Some(AirportKind::BalloonPort),
+            "closed" => Some(AirportKind::Closed),
+            _ => None,
+        }
+    }
 }
 
 impl GeoCoordTrait for AirportRefer {
@@ -34,12 +142,13 @@ impl Default for AirportRefer {
             city: String::from(""),
             country: String::from(""),
             coord: GeoCoord::default(),
+            details: AirportDetails::default(),
         }
     }
 }
 
 lazy_static! {
-    /// Airport lookup table against which you can check
+    /// Airport lookup table (bundled) against which you can check
     /// whether the specified airport really exists.
     #[derive(Debug)]
     pub static ref AIRPORT_REFERENCE: HashMap<String, AirportRefer> = {
@@ -56,9 +165,497 @@ lazy_static! {
 
         hashmap
     };
+
+    /// ISO 3166-1 codes (e.g. `SG`) to country names as
+    /// the bundled airports call them (e.g. `Singapore`),
+    /// the most common name for each code.
+    static ref COUNTRY_NAMES: HashMap<String, String> = {
+        let mut counts: HashMap<(String, String), usize> = HashMap::new();
+        AIRPORT_REFERENCE.values().for_each(|item| {
+            if let Some(code) = &item.details.iso_country {
+                *counts.entry((code.clone(), item.country.clone())).or_default() += 1;
+            }
+        });
+
+        let mut best: HashMap<String, (usize, String)> = HashMap::new();
+        counts.into_iter().for_each(|((code, name), count)| {
+            let entry = best.entry(code).or_insert((0, String::new()));
+            if (count, &name) > (entry.0, &entry.1) {
+                *entry = (count, name);
+            }
+        });
+
+        best.into_iter()
+            .map(|(code, (_, name))| (code, name))
+            .collect()
+    };
+
+    /// The database lookups go to. Replaced as a whole
+    /// (never mutated) so that readers holding
+    /// the previous one are not affected.
+    static ref ACTIVE_AIRPORT_DATABASE: RwLock<Arc<AirportDatabase>> =
+        RwLock::new(Arc::new(AirportDatabase::bundled()));
+}
+
+/// Airports keyed by ICAO code, with an index by IATA code,
+/// and a spatial index (for `nearest`, `within_radius`
+/// and `within_bounds`).
+#[derive(Debug, Clone, Default)]
+pub struct AirportDatabase {
+    airports: HashMap<String, AirportRefer>,
+    // IATA code to ICAO code.
+    iata_index: HashMap<String, String>,
+    spatial_index: SpatialIndex<AirportRefer>,
+}
+
+/// An airport found by `AirportDatabase::nearest`
+/// or `AirportDatabase::within_radius`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AirportNeighbor {
+    #[serde(flatten)]
+    pub airport: AirportRefer,
+    pub distance: f64, // In km
+}
+
+impl From<Neighbor<'_, AirportRefer>> for AirportNeighbor {
+    fn from(neighbor: Neighbor<'_, AirportRefer>) -> Self {
+        AirportNeighbor {
+            airport: neighbor.item.clone(),
+            distance: neighbor.distance,
+        }
+    }
+}
+
+impl AirportDatabase {
+    /// Some airports have no IATA code (`N/A`), and a few
+    /// IATA codes are shared (in which case, the smaller
+    /// ICAO code wins).
+    pub fn new(airports: impl IntoIterator<Item = AirportRefer>) -> Self {
+        let mut airports: Vec<AirportRefer> = airports.into_iter().collect();
+        airports.sort_by(|a, b| a.icao.cmp(&b.icao));
+
+        let mut db = AirportDatabase::default();
+        airports.into_iter().for_each(|item| {
+            if has_iata(&item) {
+                db.iata_index
+                    .entry(item.iata.clone())
+                    .or_insert_with(|| item.icao.clone());
+            }
+            db.airports.insert(item.icao.clone(), item);
+        });
+
+        db.reindex();
+        db
+    }
+
+    /// The one made of `json/airports.json`.
+    pub fn bundled() -> Self {
+        Self::new(AIRPORT_REFERENCE.values().cloned())
+    }
+
+    /// Made of OurAirports `airports.csv`. Columns are found
+    /// by the header, and only `ident`, `name`, `latitude_deg`
+    /// and `longitude_deg` are required. Closed airports
+    /// are skipped. Since the CSV has no country names,
+    /// `country` is the name the bundled airports use
+    /// for `iso_country` (or the code itself when unknown).
+    pub fn from_ourairports_csv(csv: &str) -> Result<Self, FlightPackError> {
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+
+        let headers: Vec<String> = reader.headers()
+            .map_err(|err| csv_error(&err, &[]))?
+            .iter()
+            .map(String::from)
+            .collect();
+
+        let mut airports: Vec<AirportRefer> = vec![];
+
+        for row in reader.deserialize::<OurAirportsRow>() {
+            let row: OurAirportsRow = row.map_err(|err| csv_error(&err, &headers))?;
+            if AirportKind::from_name(&row.kind) == Some(AirportKind::Closed) {
+                continue;
+            }
+            airports.push(row.into());
+        }
+
+        Ok(Self::new(airports))
+    }
+
+    /// Airports in `overrides` win over ours
+    /// (for both ICAO and IATA codes), except for
+    /// the fields they do not know (e.g. `tz`, or `country`
+    /// when they only have the ISO code).
+    pub fn merge(mut self, overrides: AirportDatabase) -> Self {
+        overrides.airports.into_iter().for_each(|(icao, mut item)| {
+            if let Some(old) = self.airports.get(&icao) {
+                if self.iata_index.get(&old.iata) == Some(&icao) {
+                    self.iata_index.remove(&old.iata);
+                }
+                if !has_country_name(&item) {
+                    item.country = old.country.clone();
+                }
+                item.details.fill_missing_from(&old.details);
+            }
+            if has_iata(&item) {
+                self.iata_index.insert(item.iata.clone(), icao.clone());
+            }
+            self.airports.insert(icao, item);
+        });
+
+        self.reindex();
+        self
+    }
+
+    // Rebuilds the spatial index (in the order of ICAO codes
+    // so that ties are always broken the same way).
+    fn reindex(&mut self) {
+        let mut airports: Vec<AirportRefer> = self.airports.values().cloned().collect();
+        airports.sort_by(|a, b| a.icao.cmp(&b.icao));
+        self.spatial_index = SpatialIndex::new(airports);
+    }
+
+    /// `k` nearest airports from `coord`, the nearest first.
+    pub fn nearest(&self, coord: &GeoCoord, k: usize) -> Vec<AirportNeighbor> {
+        self.spatial_index.nearest(coord, k)
+            .into_iter()
+            .map(AirportNeighbor::from)
+            .collect()
+    }
+
+    /// Airports within `km` from `coord`, the nearest first.
+    pub fn within_radius(&self, coord: &GeoCoord, km: f64) -> Vec<AirportNeighbor> {
+        self.spatial_index.within_radius(coord, km)
+            .into_iter()
+            .map(AirportNeighbor::from)
+            .collect()
+    }
+
+    /// Airports inside `bounds`, from the south to the north.
+    pub fn within_bounds(&self, bounds: &LatLngBounds) -> Vec<AirportRefer> {
+        self.spatial_index.within_bounds(bounds)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.airports.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.airports.is_empty()
+    }
+
+    /// Whether any airport has its `type` (the bundled one
+    /// does not, but OurAirports does).
+    pub fn has_kinds(&self) -> bool {
+        self.airports.values().any(|a| a.details.kind.is_some())
+    }
+
+    pub fn airports(&self) -> impl Iterator<Item = &AirportRefer> {
+        self.airports.values()
+    }
+
+    pub fn get(&self, icao: &str) -> Option<&AirportRefer> {
+        self.airports.get(icao)
+    }
+
+    pub fn get_by_iata(&self, iata: &str) -> Option<&AirportRefer> {
+        self.iata_index
+            .get(&iata.to_uppercase())
+            .and_then(|icao| self.airports.get(icao))
+    }
+
+    /// `code` may be either ICAO (4 letters) or IATA (3 letters).
+    pub fn lookup(&self, code: &str) -> Option<&AirportRefer> {
+        let code: String = code.trim().to_uppercase();
+
+        match code.len() {
+            4 => self.get(&code),
+            3 => self.get_by_iata(&code),
+            _ => None,
+        }
+    }
+}
+
+/// A row in OurAirports `airports.csv`
+/// (only the columns we want).
+#[derive(Debug, Deserialize)]
+struct OurAirportsRow {
+    ident: String,
+    #[serde(rename = "type", default)]
+    kind: String, // large_airport, heliport, closed, ...
+    name: String,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    #[serde(default)]
+    elevation_ft: Option<i32>,
+    #[serde(default)]
+    municipality: String,
+    #[serde(default)]
+    iso_country: String,
+    #[serde(default)]
+    iso_region: String,
+    #[serde(default)]
+    scheduled_service: String, // yes or no
+    #[serde(default)]
+    iata_code: String,
+    // Not in older files (and then, `ident` is ICAO).
+    #[serde(default)]
+    icao_code: String,
+}
+
+impl From<OurAirportsRow> for AirportRefer {
+    fn from(row: OurAirportsRow) -> Self {
+        let country: String = COUNTRY_NAMES.get(&row.iso_country)
+            .cloned()
+            .unwrap_or_else(|| row.iso_country.clone());
+        let icao: String = if row.icao_code.is_empty() {
+            row.ident
+        } else {
+            row.icao_code
+        };
+
+        let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+
+        AirportRefer {
+            icao,
+            iata: row.iata_code,
+            name: row.name,
+            city: row.municipality,
+            country,
+            coord: GeoCoord::new(row.latitude_deg, row.longitude_deg),
+            details: AirportDetails {
+                tz: None,
+                elevation: row.elevation_ft,
+                kind: AirportKind::from_name(&row.kind),
+                iso_country: non_empty(&row.iso_country),
+                iso_region: non_empty(&row.iso_region),
+                scheduled_service: match row.scheduled_service.as_str() {
+                    "yes" => Some(true),
+                    "no" => Some(false),
+                    _ => None,
+                },
+            },
+        }
+    }
+}
+
+// Tells which row (as line number, the header being 1)
+// and which column it was.
+fn csv_error(err: &csv::Error, headers: &[String]) -> FlightPackError {
+    let row: String = err.position()
+        .map_or_else(|| "header".into(), |pos| format!("row {}", pos.line()));
+
+    let column: Option<&String> = match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.field()
+            .and_then(|i| headers.get(i as usize)),
+        _ => None,
+    };
+
+    let message: String = match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.kind().to_string(),
+        _ => err.to_string(),
+    };
+
+    FlightPackError::Decode {
+        source: "airports.csv".into(),
+        field: match column {
+            Some(column) => format!("{} ({})", row, column),
+            None => row,
+        },
+        message,
+    }
+}
+
+/// The database lookups currently go to.
+pub fn active_airport_database() -> Arc<AirportDatabase> {
+    ACTIVE_AIRPORT_DATABASE
+        .read()
+        .map(|db| db.clone())
+        .unwrap_or_else(|err| err.into_inner().clone())
+}
+
+pub fn set_active_airport_database(db: AirportDatabase) {
+    let mut active = ACTIVE_AIRPORT_DATABASE
+        .write()
+        .unwrap_or_else(|err| err.into_inner());
+    *active = Arc::new(db);
+}
+
+// Whether `country` is a name (rather than
+// empty or just the ISO code).
+fn has_country_name(airport: &AirportRefer) -> bool {
+    !airport.country.is_empty() &&
+        airport.details.iso_country.as_ref()
+            .is_none_or(|code| !airport.country.eq_ignore_ascii_case(code))
+}
+
+pub fn has_iata(airport: &AirportRefer) -> bool {
+    !airport.iata.is_empty() && airport.iata != "N/A"
 }
 
 /// See if the specified airport is in the table.
 pub fn lookup_airport_database(icao: &str) -> Option<AirportRefer> {
-    AIRPORT_REFERENCE.get(icao).cloned()
+    active_airport_database().get(icao).cloned()
+}
+
+/// Same as `lookup_airport_database`, but by IATA code.
+pub fn lookup_airport_by_iata(iata: &str) -> Option<AirportRefer> {
+    active_airport_database().get_by_iata(iata).cloned()
+}
+
+/// `code` may be either ICAO (4 letters) or IATA (3 letters).
+pub fn lookup_airport(code: &str) -> Option<AirportRefer> {
+    active_airport_database().lookup(code).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::dimension::geo::great_circle_distance;
+
+    #[test]
+    fn looks_up_by_either_code() {
+        assert_eq!(lookup_airport("WSSS").unwrap().iata, "SIN");
+        assert_eq!(lookup_airport("sin").unwrap().icao, "WSSS");
+        assert_eq!(lookup_airport_by_iata("HKG").unwrap().icao, "VHHH");
+        assert!(lookup_airport_by_iata("N/A").is_none());
+        assert!(lookup_airport("SINGAPORE").is_none());
+    }
+
+    #[test]
+    fn formats_local_time() {
+        let t = Utc.with_ymd_and_hms(2022, 8, 24, 8, 25, 0).unwrap();
+        let changi = lookup_airport("WSSS").unwrap();
+        assert_eq!(changi.details.iso_country.as_deref(), Some("SG"));
+        assert_eq!(changi.details.local_time(&t), "16:25 +08");
+
+        assert_eq!(format_local_time(&t, Some("America/Los_Angeles")), "01:25 PDT");
+        assert_eq!(format_local_time(&t, Some("Mars/Olympus_Mons")), "08:25 UTC");
+        assert_eq!(format_local_time(&t, None), "08:25 UTC");
+    }
+
+    #[test]
+    fn bundled_details_are_partial() {
+        // See the top of this file for what is bundled.
+        let db = AirportDatabase::bundled();
+        assert!(db.airports().all(|a| a.details.iso_country.is_some()));
+        assert!(db.airports().filter(|a| a.details.tz.is_some()).count() > db.len() * 4 / 5);
+        assert!(db.airports().all(|a| {
+            a.details.elevation.is_none() && a.details.kind.is_none() && a.details.iso_region.is_none()
+        }));
+        assert!(!db.has_kinds());
+    }
+
+    #[test]
+    fn spatial_queries_match_linear_scan() {
+        let db = AirportDatabase::bundled();
+        let changi = GeoCoord::new(1.356, 103.987);
+
+        let mut expected: Vec<(f64, &str)> = db.airports()
+            .map(|a| (great_circle_distance(&changi, &a.coord), a.icao.as_str()))
+            .collect();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(b.1)));
+
+        let nearest: Vec<String> = db.nearest(&changi, 5).into_iter()
+            .map(|n| n.airport.icao)
+            .collect();
+        assert_eq!(nearest[0], "WSSS");
+        assert_eq!(nearest, expected[..5].iter().map(|e| e.1).collect::<Vec<_>>());
+
+        let within = db.within_radius(&changi, 500.0);
+        assert_eq!(within.len(), expected.iter().filter(|e| e.0 <= 500.0).count());
+        assert!(within.iter().all(|n| n.distance <= 500.0));
+
+        let bounds = LatLngBounds::new(1.5, 104.1, 1.2, 103.6);
+        let icaos: Vec<String> = db.within_bounds(&bounds).into_iter().map(|a| a.icao).collect();
+        assert!(icaos.contains(&"WSSS".to_string()));
+        assert_eq!(icaos.len(), db.airports().filter(|a| bounds.contains(&a.coord)).count());
+    }
+
+    const CSV: &str = "\
+id,ident,type,name,latitude_deg,longitude_deg,elevation_ft,iso_country,iso_region,municipality,scheduled_service,iata_code
+1,WSSS,large_airport,Changi Airport,1.35019,103.994003,22,SG,SG-04,Singapore,yes,SIN
+2,XXXX,closed,Somewhere Closed,1.0,100.0,,SG,SG-01,,no,
+3,WSSL,small_airport,Seletar Airport,1.41695,103.86799,,SG,SG-02,Singapore,,XSP
+";
+
+    #[test]
+    fn builds_from_ourairports_csv() {
+        let db = AirportDatabase::from_ourairports_csv(CSV).unwrap();
+        assert_eq!(db.len(), 2);
+
+        let changi = db.lookup("sin").unwrap();
+        assert_eq!(changi.name, "Changi Airport");
+        assert_eq!(changi.city, "Singapore");
+        // Named as the bundled airports do.
+        assert_eq!(changi.country, "Singapore");
+        assert_eq!(changi.details.iso_country.as_deref(), Some("SG"));
+        assert_eq!(changi.coord.lat, 1.35019);
+        assert_eq!(changi.details.elevation, Some(22));
+        assert_eq!(changi.details.kind, Some(AirportKind::LargeAirport));
+        assert_eq!(changi.details.iso_region.as_deref(), Some("SG-04"));
+        assert_eq!(changi.details.scheduled_service, Some(true));
+        assert_eq!(db.get("WSSL").unwrap().details.elevation, None);
+        assert_eq!(db.get("WSSL").unwrap().details.scheduled_service, None);
+        assert!(db.has_kinds());
+        assert!(db.get("XXXX").is_none());
+    }
+
+    #[test]
+    fn csv_errors_name_the_row() {
+        let csv = "ident,name,latitude_deg,longitude_deg\nWSSS,Changi,1.35,103.99\nWSSL,Seletar,north,103.86\n";
+        match AirportDatabase::from_ourairports_csv(csv).unwrap_err() {
+            FlightPackError::Decode { source, field, .. } => {
+                assert_eq!(source, "airports.csv");
+                assert_eq!(field, "row 3 (latitude_deg)");
+            },
+            err => panic!("Expected a decode error: {:?}", err),
+        }
+
+        let csv = "ident,latitude_deg,longitude_deg\nWSSS,1.35,103.99\n";
+        assert!(AirportDatabase::from_ourairports_csv(csv).is_err());
+    }
+
+    #[test]
+    fn overrides_win_when_merged() {
+        let db = AirportDatabase::bundled()
+            .merge(AirportDatabase::from_ourairports_csv(CSV).unwrap());
+
+        assert_eq!(db.len(), AIRPORT_REFERENCE.len());
+        assert_eq!(db.get("WSSS").unwrap().name, "Changi Airport");
+        // Time zone is kept from the bundled one.
+        assert_eq!(db.get("WSSS").unwrap().details.tz.as_deref(), Some("Asia/Singapore"));
+        assert_eq!(db.lookup("SIN").unwrap().name, "Changi Airport");
+        assert_eq!(db.lookup("XSP").unwrap().name, "Seletar Airport");
+        assert_eq!(db.lookup("HKG").unwrap().icao, "VHHH");
+    }
+
+    #[test]
+    fn country_names_survive_merge() {
+        let db = AirportDatabase::bundled()
+            .merge(AirportDatabase::from_ourairports_csv(CSV).unwrap());
+        assert_eq!(db.get("WSSS").unwrap().country, "Singapore");
+
+        // Only the code (e.g. from a country we have never named).
+        let changi = AirportRefer {
+            icao: "WSSS".into(),
+            name: "Changi Airport".into(),
+            country: "SG".into(),
+            details: AirportDetails {
+                iso_country: Some("SG".into()),
+                ..AirportDetails::default()
+            },
+            ..AirportRefer::default()
+        };
+        let db = AirportDatabase::bundled().merge(AirportDatabase::new(vec![changi]));
+        assert_eq!(db.get("WSSS").unwrap().name, "Changi Airport");
+        assert_eq!(db.get("WSSS").unwrap().country, "Singapore");
+
+        let csv = "ident,name,latitude_deg,longitude_deg,iso_country\nZZZZ,Nowhere,0,0,ZZ\n";
+        let db = AirportDatabase::from_ourairports_csv(csv).unwrap();
+        assert_eq!(db.get("ZZZZ").unwrap().country, "ZZ");
+    }
 }
@@ -0,0 +1,262 @@
+/// Searches airports in our Airport Database so that
+/// users can type "Changi" or "SIN" instead of `WSSS`.
+///
+/// Each word in the query is scored against codes (ICAO
+/// and IATA), name, city and country, and all words must
+/// match something. From the best to the worst:
+///
+/// (1) The exact code (`SIN`, `WSSS`)
+/// (2) The exact name, city or country
+/// (3) The beginning of the name, city or country
+/// (4) The beginning of a word (`Changi` in `Singapore Changi`)
+/// (5) Anywhere (`angi` in `Singapore Changi`)
+/// (6) A word with a typo or two (`Chengi`)
+/// (7) A word having the letters in order (`chg`)
+///
+/// Matching names counts more than cities,
+/// and cities more than countries.
+
+use serde::Serialize;
+
+use crate::aviation::reference::{AirportRefer, has_iata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AirportMatchField {
+    Icao,
+    Iata,
+    Name,
+    City,
+    Country,
+}
+
+impl AirportMatchField {
+    // Out of 10.
+    fn weight(&self) -> u32 {
+        match self {
+            AirportMatchField::Icao | AirportMatchField::Iata => 10,
+            AirportMatchField::Name => 10,
+            AirportMatchField::City => 9,
+            AirportMatchField::Country => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AirportSearchResult {
+    #[serde(flatten)]
+    pub airport: AirportRefer,
+    pub score: u32,
+    // Where the best matching word was found.
+    pub matched: AirportMatchField,
+}
+
+/// Returns at most `limit` airports matching `query`,
+/// the best match first.
+pub fn search_airports<'a>(
+    airports: impl IntoIterator<Item = &'a AirportRefer>,
+    query: &str,
+    limit: usize,
+) -> Vec<AirportSearchResult> {
+    let terms: Vec<String> = words(query);
+
+    if terms.is_empty() || limit == 0 {
+        return vec![];
+    }
+
+    let mut results: Vec<AirportSearchResult> = airports
+        .into_iter()
+        .filter_map(|airport| score_airport(airport, &terms))
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score.cmp(&a.score)
+            .then_with(|| a.airport.name.len().cmp(&b.airport.name.len()))
+            .then_with(|| a.airport.icao.cmp(&b.airport.icao))
+    });
+    results.truncate(limit);
+    results
+}
+
+fn score_airport(airport: &AirportRefer, terms: &[String]) -> Option<AirportSearchResult> {
+    let mut total: u32 = 0;
+    let mut best: Option<(u32, AirportMatchField)> = None;
+
+    for term in terms {
+        let (score, field) = score_term(airport, term)?;
+        total += score;
+        if best.is_none_or(|(s, _)| score > s) {
+            best = Some((score, field));
+        }
+    }
+
+    best.map(|(_, matched)| AirportSearchResult {
+        airport: airport.clone(),
+        score: total,
+        matched,
+    })
+}
+
+// The best score for `term` among all the fields
+// (or `None` when nothing matched).
+fn score_term(airport: &AirportRefer, term: &str) -> Option<(u32, AirportMatchField)> {
+    let mut candidates: Vec<(u32, AirportMatchField)> = vec![];
+
+    if airport.icao.eq_ignore_ascii_case(term) {
+        candidates.push((1000, AirportMatchField::Icao));
+    }
+    if has_iata(airport) && airport.iata.eq_ignore_ascii_case(term) {
+        candidates.push((1000, AirportMatchField::Iata));
+    }
+
+    for (field, text) in [
+        (AirportMatchField::Name, &airport.name),
+        (AirportMatchField::City, &airport.city),
+        (AirportMatchField::Country, &airport.country),
+    ].iter() {
+        let score: u32 = score_text(&text.to_lowercase(), term);
+        if score > 0 {
+            candidates.push((score * field.weight() / 10, *field));
+        }
+    }
+
+    candidates.into_iter().max_by_key(|(score, _)| *score)
+}
+
+// `text` and `term` must be in lowercase.
+fn score_text(text: &str, term: &str) -> u32 {
+    if text == term {
+        return 800;
+    }
+    if text.starts_with(term) {
+        return 600;
+    }
+
+    let text_words: Vec<String> = words(text);
+
+    if text_words.iter().any(|w| w.starts_with(term)) {
+        return 500;
+    }
+    if text.contains(term) {
+        return 300;
+    }
+
+    let term_len: usize = term.chars().count();
+    let max_typos: usize = match term_len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    };
+
+    let typos: Option<usize> = text_words.iter()
+        .map(|w| edit_distance(w, term))
+        .filter(|d| *d <= max_typos)
+        .min();
+
+    if let Some(typos) = typos {
+        return 200 - 50 * typos as u32;
+    }
+
+    if term_len >= 3 && text_words.iter().any(|w| is_subsequence(term, w)) {
+        return 50;
+    }
+
+    0
+}
+
+// Lowercased words without punctuations.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut curr: Vec<usize> = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost: usize = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost)
+                .min(prev[j + 1] + 1)
+                .min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
+// Whether all the letters in `needle` appear in `haystack` in order.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut rest = haystack.chars();
+    needle.chars().all(|c| rest.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::reference::AIRPORT_REFERENCE;
+
+    fn search(query: &str) -> Vec<String> {
+        search_airports(AIRPORT_REFERENCE.values(), query, 5)
+            .into_iter()
+            .map(|r| r.airport.icao)
+            .collect()
+    }
+
+    #[test]
+    fn finds_by_code_or_name() {
+        assert_eq!(search("SIN")[0], "WSSS");
+        assert_eq!(search("wsss")[0], "WSSS");
+        assert_eq!(search("Changi")[0], "WSSS");
+        assert_eq!(search("hong kong")[0], "VHHH");
+        assert_eq!(search("Tokyo International")[0], "RJTT");
+    }
+
+    #[test]
+    fn tolerates_typos() {
+        assert_eq!(search("Chengi")[0], "WSSS");
+        assert!(search("xqzv").is_empty());
+    }
+
+    #[test]
+    fn ranks_names_over_countries() {
+        let airports = [
+            AirportRefer {
+                icao: "AAAA".into(),
+                name: "Somewhere".into(),
+                country: "Singapore".into(),
+                ..AirportRefer::default()
+            },
+            AirportRefer {
+                icao: "BBBB".into(),
+                name: "Singapore Seletar".into(),
+                country: "Singapore".into(),
+                ..AirportRefer::default()
+            },
+        ];
+
+        let results = search_airports(airports.iter(), "singapore", 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].airport.icao, "BBBB");
+        assert_eq!(results[0].matched, AirportMatchField::Name);
+        assert_eq!(results[1].matched, AirportMatchField::Country);
+
+        assert_eq!(search_airports(airports.iter(), "singapore", 1).len(), 1);
+        assert!(search_airports(airports.iter(), "  ", 10).is_empty());
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("changi", "changi"), 0);
+        assert_eq!(edit_distance("changi", "chengi"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert!(is_subsequence("chg", "changi"));
+        assert!(!is_subsequence("gch", "changi"));
+    }
+}
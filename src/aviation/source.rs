@@ -0,0 +1,510 @@
+/// Where arrivals/departures come from. `Manager` does not
+/// care whether flights are fetched from FlightAware API,
+/// read from the bundled JSON files, or prepared by tests.
+/// It only talks to `FlightDataSource`, and `App` decides
+/// which one to use at runtime.
+
+use futures::FutureExt;
+use futures::future::LocalBoxFuture;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use web_sys::console;
+
+use crate::aviation::arrival::{
+    AeroArrivalsActualRawData,
+    AeroArrivalsRawData,
+    AeroDeparturesRawData,
+    AeroFlightsRawData,
+    AeroPagedRawData,
+};
+use crate::constants::{
+    AERO_API_URL,
+    AERO_API_KEY,
+};
+use crate::error::{FlightPackError, decode_json};
+use crate::request::fetch;
+
+pub type SourceFuture<'a, T> = LocalBoxFuture<'a, Result<T, FlightPackError>>;
+
+/// Anything that can give us arrivals/departures for an airport,
+/// and the detail for a single flight. For arrivals/departures,
+/// implementations follow `links.next` up to `max_pages`.
+pub trait FlightDataSource: Debug {
+    fn arrivals(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroArrivalsRawData>;
+
+    fn departures(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroDeparturesRawData>;
+
+    fn flight(
+        &self,
+        fa_flight_id: String,
+    ) -> SourceFuture<'_, AeroArrivalsActualRawData>;
+
+    /// `true` when the data was recorded some time ago
+    /// (so that we want to shift it to today).
+    fn is_recorded(&self) -> bool {
+        false
+    }
+}
+
+/// `App` receives the name of the source from JS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FlightDataSourceKind {
+    #[default]
+    Fixture,
+    AeroApi,
+}
+
+impl FlightDataSourceKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fixture" | "dummy" => Some(FlightDataSourceKind::Fixture),
+            "aeroapi" | "live" => Some(FlightDataSourceKind::AeroApi),
+            _ => None,
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn FlightDataSource> {
+        match self {
+            FlightDataSourceKind::Fixture => Box::new(FixtureSource::new()),
+            FlightDataSourceKind::AeroApi => Box::new(AeroApiSource::new()),
+        }
+    }
+}
+
+pub fn arrivals_path(icao: &str) -> String {
+    format!("/airports/{}/flights/arrivals", icao)
+}
+
+pub fn departures_path(icao: &str) -> String {
+    format!("/airports/{}/flights/departures", icao)
+}
+
+pub fn flight_path(fa_flight_id: &str) -> String {
+    format!("/flights/{}", fa_flight_id)
+}
+
+/// Fetch the first page for `path` using `get_page`, and keep
+/// following `links.next` until there are no more pages,
+/// or until we reach `max_pages`. When we stopped because
+/// of the limit, `has_more()` for the result is `true`.
+pub async fn follow_pages<T, F, Fut>(
+    path: String,
+    max_pages: usize,
+    get_page: F,
+) -> Result<T, FlightPackError>
+where
+    T: AeroPagedRawData,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, FlightPackError>>,
+{
+    let mut data: T = get_page(path).await?;
+    let mut pages: usize = 1;
+
+    while pages < max_pages {
+        match data.next_page() {
+            Some(next) => {
+                let page: T = get_page(next).await?;
+                data.append_page(page);
+                pages += 1;
+            },
+            None => break,
+        }
+    }
+
+    Ok(data)
+}
+
+fn first_flight(
+    data: AeroFlightsRawData,
+    fa_flight_id: &str,
+) -> Result<AeroArrivalsActualRawData, FlightPackError> {
+    data.flights
+        .into_iter()
+        .find(|f| f.fa_flight_id == fa_flight_id)
+        .ok_or_else(|| FlightPackError::MissingFixture {
+            path: flight_path(fa_flight_id),
+        })
+}
+
+lazy_static! {
+    /// Fixture pages are keyed by the very paths
+    /// we would request to FlightAware API, so that
+    /// `links.next` in one page leads to another.
+    #[derive(Debug)]
+    pub static ref FIXTURE_PAGES: HashMap<String, &'static str> = {
+        let mut hashmap = HashMap::new();
+
+        // SGN (Tan Son Nhat, Saigon)
+        hashmap.insert(
+            "/airports/VVTS/flights/arrivals".into(),
+            include_str!("../../json/arrivals_saigon.json"),
+        );
+        hashmap.insert(
+            "/airports/VVTS/flights/departures".into(),
+            include_str!("../../json/departures_saigon.json"),
+        );
+
+        // TPE (Taiwan Taoyuan, Taipei)
+        hashmap.insert(
+            "/airports/RCTP/flights/arrivals".into(),
+            include_str!("../../json/arrivals_taiwan.json"),
+        );
+        hashmap.insert(
+            "/airports/RCTP/flights/departures".into(),
+            include_str!("../../json/departures_taiwan.json"),
+        );
+
+        // HKG (Hong Kong)
+        hashmap.insert(
+            "/airports/VHHH/flights/arrivals".into(),
+            include_str!("../../json/arrivals_hongkong.json"),
+        );
+        hashmap.insert(
+            "/airports/VHHH/flights/departures".into(),
+            include_str!("../../json/departures_hongkong.json"),
+        );
+
+        // SIN (Changi, Singapore)
+        hashmap.insert(
+            "/airports/WSSS/flights/arrivals".into(),
+            include_str!("../../json/arrivals_changi.json"),
+        );
+        hashmap.insert(
+            "/airports/WSSS/flights/arrivals?cursor=83b5c87f25b6c5d".into(),
+            include_str!("../../json/arrivals_changi_2.json"),
+        );
+        hashmap.insert(
+            "/airports/WSSS/flights/departures".into(),
+            include_str!("../../json/departures_changi.json"),
+        );
+
+        // MNL (Manila, Philippines)
+        hashmap.insert(
+            "/airports/RPLL/flights/arrivals".into(),
+            include_str!("../../json/arrivals_manila.json"),
+        );
+        hashmap.insert(
+            "/airports/RPLL/flights/departures".into(),
+            include_str!("../../json/departures_manila.json"),
+        );
+
+        hashmap
+    };
+}
+
+/// Serves the JSON files bundled in the app.
+/// FlightAware API does not allow client apps
+/// to fetch data, so this is what the demo uses.
+#[derive(Debug, Default, Clone)]
+pub struct FixtureSource {}
+
+impl FixtureSource {
+    pub fn new() -> Self {
+        FixtureSource {}
+    }
+
+    fn page<T: DeserializeOwned>(path: &str) -> Result<T, FlightPackError> {
+        match FIXTURE_PAGES.get(path) {
+            Some(json) => decode_json::<T>(json, path),
+            None => Err(FlightPackError::MissingFixture { path: path.into() }),
+        }
+    }
+}
+
+impl FlightDataSource for FixtureSource {
+    fn arrivals(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroArrivalsRawData> {
+        follow_pages(
+            arrivals_path(&icao),
+            max_pages,
+            |path| async move { FixtureSource::page(&path) },
+        ).boxed_local()
+    }
+
+    fn departures(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroDeparturesRawData> {
+        follow_pages(
+            departures_path(&icao),
+            max_pages,
+            |path| async move { FixtureSource::page(&path) },
+        ).boxed_local()
+    }
+
+    /// There is no fixture for flight details, so we look
+    /// for the flight in all the bundled arrivals/departures.
+    fn flight(
+        &self,
+        fa_flight_id: String,
+    ) -> SourceFuture<'_, AeroArrivalsActualRawData> {
+        async move {
+            FIXTURE_PAGES.iter()
+                .filter(|(path, _)| path.contains("/flights/arrivals"))
+                .filter_map(|(path, _)| {
+                    FixtureSource::page::<AeroArrivalsRawData>(path).ok()
+                })
+                .flat_map(|page| page.arrivals)
+                .chain(
+                    FIXTURE_PAGES.iter()
+                        .filter(|(path, _)| path.contains("/flights/departures"))
+                        .filter_map(|(path, _)| {
+                            FixtureSource::page::<AeroDeparturesRawData>(path).ok()
+                        })
+                        .flat_map(|page| page.departures)
+                )
+                .find(|f| f.fa_flight_id == fa_flight_id)
+                .ok_or_else(|| FlightPackError::MissingFixture {
+                    path: flight_path(&fa_flight_id),
+                })
+        }.boxed_local()
+    }
+
+    fn is_recorded(&self) -> bool {
+        true
+    }
+}
+
+/// Fetches from FlightAware API over HTTP.
+#[derive(Debug, Default, Clone)]
+pub struct AeroApiSource {}
+
+impl AeroApiSource {
+    pub fn new() -> Self {
+        AeroApiSource {}
+    }
+
+    async fn page<T: DeserializeOwned>(path: String) -> Result<T, FlightPackError> {
+        let url: String = format!("{}{}", AERO_API_URL, path);
+        let mut headers: HashMap<String, String> = HashMap::new();
+
+        headers.insert(
+            "X-Apikey".into(),
+            AERO_API_KEY.to_string(),
+        );
+
+        let res: Result<T, FlightPackError> = match fetch(
+            url.as_str(),
+            Some(headers),
+        ).await {
+            Ok(json) => decode_json::<T>(&json, &url),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = &res {
+            console::error_1(&(err.to_string().into()));
+        }
+
+        res
+    }
+}
+
+impl FlightDataSource for AeroApiSource {
+    fn arrivals(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroArrivalsRawData> {
+        follow_pages(
+            arrivals_path(&icao),
+            max_pages,
+            AeroApiSource::page,
+        ).boxed_local()
+    }
+
+    fn departures(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroDeparturesRawData> {
+        follow_pages(
+            departures_path(&icao),
+            max_pages,
+            AeroApiSource::page,
+        ).boxed_local()
+    }
+
+    fn flight(
+        &self,
+        fa_flight_id: String,
+    ) -> SourceFuture<'_, AeroArrivalsActualRawData> {
+        async move {
+            let data: AeroFlightsRawData =
+                AeroApiSource::page(flight_path(&fa_flight_id)).await?;
+            first_flight(data, &fa_flight_id)
+        }.boxed_local()
+    }
+}
+
+/// Holds pages in memory. Tests populate it
+/// with whatever pages they want to serve.
+#[derive(Debug, Default, Clone)]
+pub struct MemorySource {
+    pages: HashMap<String, String>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        MemorySource {
+            pages: HashMap::new(),
+        }
+    }
+
+    /// Serve `data` for `path` (which can be
+    /// any of the paths found in `links.next`).
+    pub fn insert_page<T: Serialize>(&mut self, path: &str, data: &T) {
+        self.pages.insert(
+            path.into(),
+            serde_json::to_string(data).unwrap(),
+        );
+    }
+
+    pub fn insert_arrivals(&mut self, icao: &str, data: &AeroArrivalsRawData) {
+        self.insert_page(&arrivals_path(icao), data);
+    }
+
+    pub fn insert_departures(&mut self, icao: &str, data: &AeroDeparturesRawData) {
+        self.insert_page(&departures_path(icao), data);
+    }
+
+    pub fn insert_flight(&mut self, flight: &AeroArrivalsActualRawData) {
+        self.insert_page(
+            &flight_path(&flight.fa_flight_id),
+            &AeroFlightsRawData {
+                links: None,
+                num_pages: 1,
+                flights: vec![flight.clone()],
+            },
+        );
+    }
+
+    fn page<T: DeserializeOwned>(&self, path: &str) -> Result<T, FlightPackError> {
+        match self.pages.get(path) {
+            Some(json) => decode_json::<T>(json, path),
+            None => Err(FlightPackError::MissingFixture { path: path.into() }),
+        }
+    }
+}
+
+impl FlightDataSource for MemorySource {
+    fn arrivals(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroArrivalsRawData> {
+        follow_pages(
+            arrivals_path(&icao),
+            max_pages,
+            move |path| async move { self.page(&path) },
+        ).boxed_local()
+    }
+
+    fn departures(
+        &self,
+        icao: String,
+        max_pages: usize,
+    ) -> SourceFuture<'_, AeroDeparturesRawData> {
+        follow_pages(
+            departures_path(&icao),
+            max_pages,
+            move |path| async move { self.page(&path) },
+        ).boxed_local()
+    }
+
+    fn flight(
+        &self,
+        fa_flight_id: String,
+    ) -> SourceFuture<'_, AeroArrivalsActualRawData> {
+        async move {
+            let data: AeroFlightsRawData =
+                self.page(&flight_path(&fa_flight_id))?;
+            first_flight(data, &fa_flight_id)
+        }.boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn fixture_arrivals_stops_at_page_limit() {
+        let source = FixtureSource::new();
+        let res = block_on(source.arrivals("WSSS".into(), 1)).unwrap();
+        assert_eq!(res.arrivals.len(), 15);
+        assert_eq!(res.num_pages, 1);
+        assert!(res.has_more());
+    }
+
+    #[test]
+    fn fixture_arrivals_follows_next_links() {
+        let source = FixtureSource::new();
+        let res = block_on(source.arrivals("WSSS".into(), 5)).unwrap();
+        assert_eq!(res.arrivals.len(), 21);
+        assert_eq!(res.num_pages, 2);
+        assert!(!res.has_more());
+    }
+
+    #[test]
+    fn fixture_departures_single_page() {
+        let source = FixtureSource::new();
+        let res = block_on(source.departures("VHHH".into(), 5)).unwrap();
+        assert_eq!(res.departures.len(), 11);
+        assert!(!res.has_more());
+    }
+
+    #[test]
+    fn fixture_arrivals_unknown_airport() {
+        let source = FixtureSource::new();
+        assert_eq!(
+            block_on(source.arrivals("XXXX".into(), 5)).unwrap_err(),
+            FlightPackError::MissingFixture {
+                path: "/airports/XXXX/flights/arrivals".into(),
+            },
+        );
+    }
+
+    #[test]
+    fn fixture_flight_detail() {
+        let source = FixtureSource::new();
+        let res = block_on(
+            source.flight("SIA917-1661149800-schedule-0851".into())
+        ).unwrap();
+        assert_eq!(res.ident, "SIA917");
+    }
+
+    #[test]
+    fn memory_source_serves_inserted_pages() {
+        let fixture = FixtureSource::new();
+        let page = block_on(fixture.arrivals("WSSS".into(), 1)).unwrap();
+        let flight = page.arrivals[0].clone();
+
+        let mut source = MemorySource::new();
+        source.insert_arrivals("SIN", &page);
+        source.insert_flight(&flight);
+
+        // `links.next` of the page is not in memory.
+        assert!(block_on(source.arrivals("SIN".into(), 1)).is_ok());
+        assert!(block_on(source.arrivals("SIN".into(), 2)).is_err());
+        assert!(block_on(source.departures("SIN".into(), 1)).is_err());
+
+        let res = block_on(source.flight(flight.fa_flight_id.clone())).unwrap();
+        assert_eq!(res.ident, flight.ident);
+    }
+}
@@ -0,0 +1,242 @@
+/// Summary numbers for the flights we have (see `App::stats`).
+/// They are computed once flights are set (or reset)
+/// in `Manager`, and not every frame, so filters
+/// (`App::set_filter`) do not change them.
+///
+/// Delays are in minutes (negative when early), and
+/// distances in statute miles (as FlightAware gives).
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::aviation::flight::Flight;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FlightStats {
+    pub flights: usize,
+    pub total_route_distance: i64,
+    // The busiest (arrivals + departures) first.
+    pub airports: Vec<AirportStats>,
+    // The busiest first (at most `STATS_MAX_ROUTES`).
+    pub routes: Vec<RouteStats>,
+    // The busiest first.
+    pub operators: Vec<OperatorStats>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AirportStats {
+    pub icao: String,
+    pub name: String,
+    // Whether it is one of the airports JS gave.
+    pub target: bool,
+    pub arrivals: usize,
+    pub departures: usize,
+    // Arrival delays for arrivals,
+    // and departure delays for departures.
+    pub average_delay: Option<f64>,
+    pub max_delay: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteStats {
+    pub origin: String, // ICAO
+    pub destination: String, // ICAO
+    pub flights: usize,
+    pub route_distance: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OperatorStats {
+    pub operator: String, // SIA
+    pub name: Option<String>, // Singapore Airlines
+    pub flights: usize,
+    pub total_route_distance: i64,
+}
+
+// Delays (in minutes) collected for an airport.
+#[derive(Debug, Default)]
+struct DelaySamples {
+    sum: f64,
+    count: usize,
+    max: Option<f64>,
+}
+
+impl DelaySamples {
+    fn add(&mut self, seconds: Option<i32>) {
+        if let Some(seconds) = seconds {
+            let minutes: f64 = seconds as f64 / 60.0;
+            self.sum += minutes;
+            self.count += 1;
+            self.max = Some(self.max.map_or(minutes, |max| max.max(minutes)));
+        }
+    }
+
+    fn average(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+}
+
+impl FlightStats {
+    /// `targets` are ICAO codes of the airports JS gave.
+    /// Routes are cut down to `max_routes`.
+    pub fn new(flights: &[Flight], targets: &[String], max_routes: usize) -> Self {
+        let mut airports: HashMap<String, (AirportStats, DelaySamples)> = HashMap::new();
+        let mut routes: HashMap<(String, String), RouteStats> = HashMap::new();
+        let mut operators: HashMap<String, OperatorStats> = HashMap::new();
+
+        for f in flights {
+            let orig: &str = &f.orig_airport.icao;
+            let dest: &str = &f.dest_airport.icao;
+
+            let (stats, delays) = airports
+                .entry(orig.into())
+                .or_insert_with(|| airport_stats(orig, &f.orig_airport.name, targets));
+            stats.departures += 1;
+            delays.add(f.departure_delay);
+
+            let (stats, delays) = airports
+                .entry(dest.into())
+                .or_insert_with(|| airport_stats(dest, &f.dest_airport.name, targets));
+            stats.arrivals += 1;
+            delays.add(f.arrival_delay);
+
+            let route = routes
+                .entry((orig.into(), dest.into()))
+                .or_insert_with(|| RouteStats {
+                    origin: orig.into(),
+                    destination: dest.into(),
+                    flights: 0,
+                    route_distance: f.route_distance,
+                });
+            route.flights += 1;
+
+            let operator = operators
+                .entry(f.operator.clone())
+                .or_insert_with(|| OperatorStats {
+                    operator: f.operator.clone(),
+                    name: f.airline.as_ref().map(|a| a.name.clone()),
+                    flights: 0,
+                    total_route_distance: 0,
+                });
+            operator.flights += 1;
+            operator.total_route_distance += f.route_distance as i64;
+        }
+
+        let mut airports: Vec<AirportStats> = airports
+            .into_values()
+            .map(|(mut stats, delays)| {
+                stats.average_delay = delays.average();
+                stats.max_delay = delays.max;
+                stats
+            })
+            .collect();
+        airports.sort_by(|a, b| {
+            (b.arrivals + b.departures).cmp(&(a.arrivals + a.departures))
+                .then_with(|| a.icao.cmp(&b.icao))
+        });
+
+        let mut routes: Vec<RouteStats> = routes.into_values().collect();
+        routes.sort_by(|a, b| {
+            b.flights.cmp(&a.flights)
+                .then_with(|| a.origin.cmp(&b.origin))
+                .then_with(|| a.destination.cmp(&b.destination))
+        });
+        routes.truncate(max_routes);
+
+        let mut operators: Vec<OperatorStats> = operators.into_values().collect();
+        operators.sort_by(|a, b| {
+            b.flights.cmp(&a.flights)
+                .then_with(|| a.operator.cmp(&b.operator))
+        });
+
+        FlightStats {
+            flights: flights.len(),
+            total_route_distance: flights.iter().map(|f| f.route_distance as i64).sum(),
+            airports,
+            routes,
+            operators,
+        }
+    }
+}
+
+fn airport_stats(icao: &str, name: &str, targets: &[String]) -> (AirportStats, DelaySamples) {
+    let stats = AirportStats {
+        icao: icao.into(),
+        name: name.into(),
+        target: targets.iter().any(|t| t == icao),
+        ..AirportStats::default()
+    };
+    (stats, DelaySamples::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::airline::lookup_airline;
+    use crate::aviation::arrival::AirportArrival;
+
+    fn flight(operator: &str, orig: &str, dest: &str, distance: i32, delays: (Option<i32>, Option<i32>)) -> Flight {
+        let mut f = Flight::new(AirportArrival {
+            operator: operator.into(),
+            route_distance: distance,
+            ..AirportArrival::default()
+        });
+        f.airline = lookup_airline(operator);
+        f.orig_airport.icao = orig.into();
+        f.dest_airport.icao = dest.into();
+        f.departure_delay = delays.0;
+        f.arrival_delay = delays.1;
+        f
+    }
+
+    fn stats() -> FlightStats {
+        let flights = vec![
+            flight("SIA", "VHHH", "WSSS", 1600, (Some(10 * 60), Some(20 * 60))),
+            flight("SIA", "VHHH", "WSSS", 1600, (None, Some(-4 * 60))),
+            flight("CPA", "WSSS", "VHHH", 1600, (Some(5 * 60), None)),
+            flight("CPA", "RPLL", "WSSS", 1480, (None, Some(60 * 60))),
+        ];
+        FlightStats::new(&flights, &["WSSS".into(), "VHHH".into()], 2)
+    }
+
+    #[test]
+    fn counts_per_airport() {
+        let stats = stats();
+        assert_eq!(stats.flights, 4);
+        assert_eq!(stats.total_route_distance, 6280);
+
+        let wsss = &stats.airports[0];
+        assert_eq!((wsss.icao.as_str(), wsss.arrivals, wsss.departures), ("WSSS", 3, 1));
+        assert!(wsss.target);
+        // Arrivals: 20, -4 and 60 (departure: 5).
+        assert_eq!(wsss.average_delay, Some((20.0 - 4.0 + 60.0 + 5.0) / 4.0));
+        assert_eq!(wsss.max_delay, Some(60.0));
+
+        let rpll = stats.airports.iter().find(|a| a.icao == "RPLL").unwrap();
+        assert!(!rpll.target);
+        assert_eq!(rpll.average_delay, None);
+    }
+
+    #[test]
+    fn busiest_routes_and_operators() {
+        let stats = stats();
+        assert_eq!(stats.routes, vec![
+            RouteStats { origin: "VHHH".into(), destination: "WSSS".into(), flights: 2, route_distance: 1600 },
+            RouteStats { origin: "RPLL".into(), destination: "WSSS".into(), flights: 1, route_distance: 1480 },
+        ]);
+
+        assert_eq!(stats.operators.len(), 2);
+        assert_eq!(stats.operators[0].name.as_deref(), Some("Cathay Pacific"));
+        assert_eq!(stats.operators[0].total_route_distance, 3080);
+        assert_eq!(stats.operators[1].operator, "SIA");
+    }
+
+    #[test]
+    fn empty_without_flights() {
+        assert_eq!(FlightStats::new(&[], &[], 10), FlightStats::default());
+    }
+}
@@ -0,0 +1,130 @@
+/// Where each flight is in its life. For arrivals/departures
+/// fetched, the status is derived from the flags (`cancelled`
+/// and `diverted`) and from which of the actual timestamps
+/// are filled. While animating, `Flight::advance` keeps it
+/// up to date against `Clock` (except for cancelled
+/// and diverted ones which stay as they are).
+
+use serde::{Deserialize, Serialize};
+
+use crate::aviation::arrival::AeroArrivalsActualRawData;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlightStatus {
+    #[default]
+    Scheduled,
+    Taxiing, // Pushed back from the gate, but not yet in the air.
+    Airborne,
+    Landed,
+    Diverted,
+    Cancelled,
+}
+
+impl FlightStatus {
+    pub fn from_raw(raw: &AeroArrivalsActualRawData) -> Self {
+        if raw.cancelled {
+            FlightStatus::Cancelled
+        } else if raw.diverted {
+            FlightStatus::Diverted
+        } else if raw.actual_on.is_some() || raw.actual_in.is_some() {
+            FlightStatus::Landed
+        } else if raw.actual_off.is_some() {
+            FlightStatus::Airborne
+        } else if raw.actual_out.is_some() {
+            FlightStatus::Taxiing
+        } else {
+            FlightStatus::Scheduled
+        }
+    }
+
+    /// Cancelled and diverted flights do not change
+    /// no matter what time it is.
+    pub fn is_disrupted(&self) -> bool {
+        matches!(self, FlightStatus::Cancelled | FlightStatus::Diverted)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FlightStatus::Scheduled => "Scheduled",
+            FlightStatus::Taxiing => "Taxiing",
+            FlightStatus::Airborne => "Airborne",
+            FlightStatus::Landed => "Landed",
+            FlightStatus::Diverted => "Diverted",
+            FlightStatus::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// Whether to show cancelled/diverted flights,
+/// and how. JS may give any of them
+/// (see `App::set_status_options`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusOptions {
+    pub include_cancelled: bool,
+    pub include_diverted: bool,
+    pub dash_cancelled: bool,
+}
+
+impl Default for StatusOptions {
+    fn default() -> Self {
+        StatusOptions {
+            include_cancelled: true,
+            include_diverted: true,
+            dash_cancelled: true,
+        }
+    }
+}
+
+impl StatusOptions {
+    pub fn includes(&self, status: FlightStatus) -> bool {
+        match status {
+            FlightStatus::Cancelled => self.include_cancelled,
+            FlightStatus::Diverted => self.include_diverted,
+            _ => true,
+        }
+    }
+}
+
+/// FlightAware tells where the flight actually went
+/// in its status text (e.g. `"Diverted to RJBB"`).
+/// We take the first thing after "Diverted" that
+/// looks like an ICAO code.
+pub fn diverted_to_icao(status: &str) -> Option<String> {
+    let lower: String = status.to_lowercase();
+    let start: usize = lower.find("diverted")?;
+
+    status[start..]
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .find(|word| {
+            word.len() == 4 &&
+                word.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) &&
+                word.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        })
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diverted_to_icao_from_status_text() {
+        assert_eq!(diverted_to_icao("Diverted to RJBB"), Some("RJBB".into()));
+        assert_eq!(diverted_to_icao("En Route / Diverted (VHHH)"), Some("VHHH".into()));
+        assert_eq!(diverted_to_icao("Diverted"), None);
+        assert_eq!(diverted_to_icao("RJBB Scheduled"), None);
+    }
+
+    #[test]
+    fn options_decide_which_to_include() {
+        let options = StatusOptions {
+            include_cancelled: false,
+            ..StatusOptions::default()
+        };
+        assert!(!options.includes(FlightStatus::Cancelled));
+        assert!(options.includes(FlightStatus::Diverted));
+        assert!(options.includes(FlightStatus::Airborne));
+    }
+}
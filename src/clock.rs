@@ -0,0 +1,193 @@
+/// `Clock` tells the app what time it is. Flights are drawn
+/// (and filtered) against `Clock::now()`, and never against
+/// `Utc::now()` directly, so that JS can speed up, pause,
+/// or jump to any moment (see `App::set_time_scale`,
+/// `App::pause`, `App::resume` and `App::seek`).
+///
+/// Internally, we remember an _anchor_ which is a pair
+/// of the real time and the simulated time at the moment.
+/// The simulated time goes on from the anchor
+/// at `scale` times the real speed.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::constants::CLOCK_MAX_TIME_SCALE;
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    anchor_real: DateTime<Utc>,
+    anchor_sim: DateTime<Utc>,
+    scale: f64,
+    paused: bool,
+    real_now: fn() -> DateTime<Utc>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::real()
+    }
+}
+
+impl Clock {
+    /// Goes just like the wall clock.
+    pub fn real() -> Self {
+        Self::scaled(Utc::now(), 1.0)
+    }
+
+    /// Stays at `at` until resumed.
+    pub fn fixed(at: DateTime<Utc>) -> Self {
+        let mut clock = Self::scaled(at, 1.0);
+        clock.paused = true;
+        clock
+    }
+
+    /// Starts at `start`, and goes `scale` times faster
+    /// than the wall clock.
+    pub fn scaled(start: DateTime<Utc>, scale: f64) -> Self {
+        Self::with_real_time(start, scale, Utc::now)
+    }
+
+    /// Same as `scaled`, but the wall clock is read
+    /// from `real_now` (which tests can control).
+    pub fn with_real_time(
+        start: DateTime<Utc>,
+        scale: f64,
+        real_now: fn() -> DateTime<Utc>,
+    ) -> Self {
+        Clock {
+            anchor_real: real_now(),
+            anchor_sim: start,
+            scale,
+            paused: false,
+            real_now,
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        if self.paused {
+            return self.anchor_sim;
+        }
+
+        let elapsed = (self.real_now)() - self.anchor_real;
+        let millis = (elapsed.num_milliseconds() as f64) * self.scale;
+
+        // Never beyond what `DateTime` can tell.
+        self.anchor_sim
+            .checked_add_signed(Duration::milliseconds(millis as i64))
+            .unwrap_or(self.anchor_sim)
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// `scale` must be above 0 and up to `CLOCK_MAX_TIME_SCALE`
+    /// (use `pause` to stop). Otherwise, nothing changes.
+    pub fn set_time_scale(&mut self, scale: f64) -> Result<(), String> {
+        if !(scale.is_finite() && scale > 0.0 && scale <= CLOCK_MAX_TIME_SCALE) {
+            return Err(format!(
+                "Time scale must be above 0 and up to {}: {}",
+                CLOCK_MAX_TIME_SCALE,
+                scale,
+            ));
+        }
+
+        self.reanchor();
+        self.scale = scale;
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.reanchor();
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.anchor_real = (self.real_now)();
+        self.paused = false;
+    }
+
+    pub fn seek(&mut self, at: DateTime<Utc>) {
+        self.anchor_real = (self.real_now)();
+        self.anchor_sim = at;
+    }
+
+    /// Move the anchor to the current moment
+    /// so that changes apply from now on.
+    fn reanchor(&mut self) {
+        self.anchor_sim = self.now();
+        self.anchor_real = (self.real_now)();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    static REAL_SECS: AtomicI64 = AtomicI64::new(0);
+
+    fn fake_real_now() -> DateTime<Utc> {
+        Utc.timestamp_opt(REAL_SECS.load(Ordering::SeqCst), 0).unwrap()
+    }
+
+    fn advance(secs: i64) {
+        REAL_SECS.fetch_add(secs, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn fixed_clock_does_not_move() {
+        let at = Utc.with_ymd_and_hms(2022, 8, 24, 9, 0, 0).unwrap();
+        let clock = Clock::fixed(at);
+        assert_eq!(clock.now(), at);
+        assert!(clock.is_paused());
+    }
+
+    #[test]
+    fn scaled_clock_scale_pause_resume_seek() {
+        REAL_SECS.store(1_000_000, Ordering::SeqCst);
+        let start = Utc.with_ymd_and_hms(2022, 8, 24, 9, 0, 0).unwrap();
+        let mut clock = Clock::with_real_time(start, 60.0, fake_real_now);
+
+        advance(10);
+        assert_eq!(clock.now(), start + Duration::minutes(10));
+
+        clock.set_time_scale(1.0).unwrap();
+        advance(30);
+        assert_eq!(clock.now(), start + Duration::seconds(630));
+
+        clock.pause();
+        advance(100);
+        assert_eq!(clock.now(), start + Duration::seconds(630));
+
+        clock.resume();
+        advance(5);
+        assert_eq!(clock.now(), start + Duration::seconds(635));
+
+        let at = Utc.with_ymd_and_hms(2022, 8, 23, 21, 0, 0).unwrap();
+        clock.seek(at);
+        advance(2);
+        assert_eq!(clock.now(), at + Duration::seconds(2));
+    }
+
+    #[test]
+    fn invalid_time_scales_are_rejected() {
+        let start = Utc.with_ymd_and_hms(2022, 8, 24, 9, 0, 0).unwrap();
+        let mut clock = Clock::fixed(start);
+
+        for scale in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.0, -1.0, 1e12] {
+            assert!(clock.set_time_scale(scale).is_err(), "{}", scale);
+            assert_eq!(clock.scale(), 1.0);
+        }
+
+        clock.set_time_scale(CLOCK_MAX_TIME_SCALE).unwrap();
+        clock.resume();
+        assert_eq!(clock.scale(), CLOCK_MAX_TIME_SCALE);
+        assert!(clock.now() >= start);
+    }
+}
@@ -1,14 +1,16 @@
 // use chrono::offset::Utc;
 // use chrono::DateTime;
 
-pub const DUMMY: bool = true;
-
 pub const HOST: &str = env!("HOST");
 pub const ALLOW_ORIGIN: &str = env!("ACCESS_CONTROL_ALLOW_ORIGIN");
 
 pub const AERO_API_URL: &str = "https://aeroapi.flightaware.com/aeroapi";
 pub const AERO_API_KEY: &str = env!("AERO_API_KEY");
 
+// How many pages to follow (via `links.next`)
+// for each airport's arrivals/departures.
+pub const AERO_API_MAX_PAGES: usize = 5;
+
 pub const FONT_SIZE: u8 = 16;
 pub const FONT_COLOR: &str = "#ffffff";
 pub const FONT_FAMILY: &str = "Work Sans, -apple-system, BlinkMacSystemFont, Segoe UI, Roboto, Oxygen, Ubuntu, Cantarell, Fira Sans, Droid Sans, Helvetica Neue, sans-serif";
@@ -21,3 +23,40 @@ pub const AIRPORT_TEXT_HEIGHT_DEFAULT: f64 = 30_f64;
 
 pub const FLIGHT_LINE_WIDTH: f64 = 1.0;
 pub const FLIGHT_LINE_COLOR: &str = "#ffffff";
+pub const FLIGHT_LINE_DASH: f64 = 4.0; // For cancelled flights
+pub const FLIGHT_PATH_STEP_KM: f64 = 100.0;
+pub const FLIGHT_MARKER_SIZE: f64 = 8.0;
+pub const FLIGHT_MARKER_COLOR: &str = "#ffd400";
+
+// The fastest `Clock` may go (e.g. 1 hour in 0.36 seconds).
+pub const CLOCK_MAX_TIME_SCALE: f64 = 10_000.0;
+
+// Time spent for climbing and descending (in minutes)
+// on top of cruising when estimating arrival times.
+pub const FLIGHT_CLIMB_DESCENT_MINUTES: i64 = 20;
+
+// Flight line colors for each `DelayBucket`.
+pub const DELAY_COLOR_EARLY: &str = "#7fdbff";
+pub const DELAY_COLOR_ON_TIME: &str = FLIGHT_LINE_COLOR;
+pub const DELAY_COLOR_MINOR: &str = "#ffb347";
+pub const DELAY_COLOR_MAJOR: &str = "#ff4136";
+
+// The largest (in minutes) JS may give for `DelayThresholds`.
+pub const DELAY_THRESHOLD_MAX_MINUTES: i32 = 24 * 60;
+
+pub const TOOLTIP_BG_COLOR: &str = "rgba(0, 0, 0, 0.75)";
+pub const TOOLTIP_PADDING: f64 = 8.0;
+pub const TOOLTIP_LINE_SPACING: f64 = 6.0;
+pub const TOOLTIP_OFFSET: f64 = 14.0;
+pub const TOOLTIP_MARGIN: f64 = 20.0;
+
+// How far (in CSS pixels) from flights and airports
+// a click may land for `App::pick` to find them.
+pub const PICK_TOLERANCE: f64 = 8.0;
+
+// How many airports `App::search_airports` returns
+// when JS does not say.
+pub const AIRPORT_SEARCH_LIMIT: usize = 10;
+
+// How many of the busiest routes `App::stats` returns.
+pub const STATS_MAX_ROUTES: usize = 10;
@@ -1,10 +1,14 @@
 use geoutils::Location;
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
+use std::fmt;
 
 use crate::dimension::Size;
 use crate::dimension::point::PointCoord;
-use crate::utils::deg_to_rad;
+use crate::utils::{deg_to_rad, rad_to_deg};
+
+/// Mean Earth radius (in km).
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
 
 pub trait GeoCoordTrait {
     fn get_coord(&self) -> GeoCoord;
@@ -89,6 +93,43 @@ impl LatLngBounds {
         self.south = south;
         self.west = west;
     }
+
+    /// When the map is centered on the Pacific, `west`
+    /// is greater than `east` (e.g. `west: 150, east: -120`),
+    /// because longitudes are always given in `[-180, 180]`.
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+
+    /// Width of the bounds in degrees (always positive
+    /// for valid bounds, even when crossing the antimeridian).
+    pub fn lng_span(&self) -> f64 {
+        if self.crosses_antimeridian() {
+            self.east - self.west + 360.0
+        } else {
+            self.east - self.west
+        }
+    }
+
+    pub fn center_lng(&self) -> f64 {
+        normalize_lng(self.west + self.lng_span() / 2.0)
+    }
+
+    pub fn contains(&self, coord: &GeoCoord) -> bool {
+        let offset = self.lng_offset(coord.lng);
+        coord.lat >= self.south && coord.lat <= self.north &&
+            offset >= 0.0 && offset <= self.lng_span()
+    }
+
+    /// Degrees east of `west` for `lng`. Since `lng` has
+    /// copies every 360 degrees, we pick the one closest
+    /// to the center of the bounds, so the result may be
+    /// negative or greater than `lng_span()` for points
+    /// outside the bounds.
+    pub fn lng_offset(&self, lng: f64) -> f64 {
+        let half = self.lng_span() / 2.0;
+        (lng - self.west - half + 180.0).rem_euclid(360.0) - 180.0 + half
+    }
 }
 
 impl Default for LatLngBounds {
@@ -125,32 +166,322 @@ fn mercator_y(lat: f64) -> f64 {
     ).ln()
 }
 
-pub fn get_mercator_position(
-    size: &Size,
-    bounds: &LatLngBounds,
-    coord: &GeoCoord,
-) -> PointCoord {
-    let width = size.width;
-    let height = size.height;
+/// Converts between geo coordinates and canvas pixels.
+/// Implementations are built from `Size` and `LatLngBounds`
+/// whenever either changes (see `Manager::update`),
+/// so that all the factors are computed only once.
+///
+/// Of all the copies of a longitude (one every 360 degrees),
+/// `project` takes the one closest to the center of the bounds,
+/// so that it works for bounds crossing the antimeridian.
+pub trait Projection: fmt::Debug {
+    fn size(&self) -> &Size;
+    fn bounds(&self) -> &LatLngBounds;
 
-    let north = deg_to_rad(bounds.north);
-    let south = deg_to_rad(bounds.south);
-    let east = deg_to_rad(bounds.east);
-    let west = deg_to_rad(bounds.west);
+    /// Pixel position for `lat`, where `lng_offset` is
+    /// degrees east of the west edge (see `LatLngBounds::lng_offset`).
+    fn project_at_offset(&self, lat: f64, lng_offset: f64) -> PointCoord;
 
-    let lat = deg_to_rad(coord.lat);
-    let lng = deg_to_rad(coord.lng);
+    /// Geo coordinates for a pixel position.
+    /// Longitudes are normalized into `[-180, 180]`.
+    fn unproject(&self, point: &PointCoord) -> GeoCoord;
+
+    fn project(&self, coord: &GeoCoord) -> PointCoord {
+        self.project_at_offset(coord.lat, self.bounds().lng_offset(coord.lng))
+    }
 
-    let y_min = mercator_y(south);
-    let y_max = mercator_y(north);
+    /// Projects a path as a whole. Each point is _not_ moved
+    /// to its own closest copy (which would tear the path apart
+    /// when crossing the antimeridian). Instead, the path is
+    /// unwrapped first (see `unwrap_path`), then shifted
+    /// by the same amount so that its middle point comes
+    /// closest to the center of the bounds.
+    fn project_path(&self, path: &[GeoCoord]) -> Vec<PointCoord> {
+        if path.is_empty() {
+            return vec![];
+        }
+
+        let bounds: &LatLngBounds = self.bounds();
+        let path: Vec<GeoCoord> = unwrap_path(path);
+        let mid: &GeoCoord = &path[path.len() / 2];
+        let shift: f64 = bounds.lng_offset(mid.lng) - (mid.lng - bounds.west);
+
+        path.iter()
+            .map(|coord| {
+                self.project_at_offset(coord.lat, coord.lng - bounds.west + shift)
+            })
+            .collect()
+    }
+}
+
+/// Which `Projection` to build.
+/// Google Maps uses Web Mercator, so that is the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectionKind {
+    #[default]
+    WebMercator,
+    Equirectangular,
+}
+
+impl ProjectionKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "web_mercator" | "mercator" => Some(ProjectionKind::WebMercator),
+            "equirectangular" => Some(ProjectionKind::Equirectangular),
+            _ => None,
+        }
+    }
+
+    pub fn create(
+        &self,
+        size: &Size,
+        bounds: &LatLngBounds,
+    ) -> Box<dyn Projection> {
+        match self {
+            ProjectionKind::WebMercator => {
+                Box::new(WebMercatorProjection::new(size, bounds))
+            },
+            ProjectionKind::Equirectangular => {
+                Box::new(EquirectangularProjection::new(size, bounds))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebMercatorProjection {
+    size: Size,
+    bounds: LatLngBounds,
+    y_max: f64,
+    x_factor: f64,
+    y_factor: f64,
+}
+
+impl WebMercatorProjection {
+    pub fn new(size: &Size, bounds: &LatLngBounds) -> Self {
+        let y_min = mercator_y(deg_to_rad(bounds.south));
+        let y_max = mercator_y(deg_to_rad(bounds.north));
+
+        WebMercatorProjection {
+            size: size.clone(),
+            bounds: bounds.clone(),
+            y_max,
+            x_factor: size.width / deg_to_rad(bounds.lng_span()),
+            y_factor: size.height / (y_max - y_min),
+        }
+    }
+}
+
+impl Default for WebMercatorProjection {
+    fn default() -> Self {
+        Self::new(&Size::default(), &LatLngBounds::default())
+    }
+}
+
+impl Projection for WebMercatorProjection {
+    fn size(&self) -> &Size { &self.size }
+    fn bounds(&self) -> &LatLngBounds { &self.bounds }
+
+    fn project_at_offset(&self, lat: f64, lng_offset: f64) -> PointCoord {
+        let x = deg_to_rad(lng_offset) * self.x_factor;
+        let y = (self.y_max - mercator_y(deg_to_rad(lat))) * self.y_factor;
+
+        PointCoord::new(x, y)
+    }
+
+    fn unproject(&self, point: &PointCoord) -> GeoCoord {
+        let lng_offset = rad_to_deg(point.x / self.x_factor);
+        let y = self.y_max - point.y / self.y_factor;
+        let lat = 2.0 * y.exp().atan() - PI / 2.0;
+
+        GeoCoord::new(
+            rad_to_deg(lat),
+            normalize_lng(self.bounds.west + lng_offset),
+        )
+    }
+}
+
+/// Latitudes and longitudes are simply scaled
+/// (a.k.a. "Plate Carrée").
+#[derive(Debug, Clone)]
+pub struct EquirectangularProjection {
+    size: Size,
+    bounds: LatLngBounds,
+    x_factor: f64,
+    y_factor: f64,
+}
+
+impl EquirectangularProjection {
+    pub fn new(size: &Size, bounds: &LatLngBounds) -> Self {
+        EquirectangularProjection {
+            size: size.clone(),
+            bounds: bounds.clone(),
+            x_factor: size.width / bounds.lng_span(),
+            y_factor: size.height / (bounds.north - bounds.south),
+        }
+    }
+}
+
+impl Projection for EquirectangularProjection {
+    fn size(&self) -> &Size { &self.size }
+    fn bounds(&self) -> &LatLngBounds { &self.bounds }
+
+    fn project_at_offset(&self, lat: f64, lng_offset: f64) -> PointCoord {
+        PointCoord::new(
+            lng_offset * self.x_factor,
+            (self.bounds.north - lat) * self.y_factor,
+        )
+    }
+
+    fn unproject(&self, point: &PointCoord) -> GeoCoord {
+        GeoCoord::new(
+            self.bounds.north - point.y / self.y_factor,
+            normalize_lng(self.bounds.west + point.x / self.x_factor),
+        )
+    }
+}
+
+/// Makes longitudes continuous along `path` so that
+/// no two neighbors are more than 180 degrees apart.
+/// For instance, `179, -179` becomes `179, 181`.
+/// Resulting longitudes may go beyond `[-180, 180]`.
+pub fn unwrap_path(path: &[GeoCoord]) -> Vec<GeoCoord> {
+    let mut unwrapped: Vec<GeoCoord> = Vec::with_capacity(path.len());
+
+    for coord in path {
+        let lng = match unwrapped.last() {
+            Some(prev) => prev.lng + (coord.lng - prev.lng + 180.0).rem_euclid(360.0) - 180.0,
+            None => coord.lng,
+        };
+        unwrapped.push(GeoCoord::new(coord.lat, lng));
+    }
+
+    unwrapped
+}
 
-    let x_factor = width / (east - west);
-    let y_factor = height / (y_max - y_min);
+/// Central angle (in radians) between `from` and `to`
+/// on the sphere (haversine).
+pub fn central_angle(from: &GeoCoord, to: &GeoCoord) -> f64 {
+    let lat1 = deg_to_rad(from.lat);
+    let lat2 = deg_to_rad(to.lat);
+    let d_lat = lat2 - lat1;
+    let d_lng = deg_to_rad(to.lng - from.lng);
 
-    let x = (lng - west) * x_factor;
-    let y = (y_max - mercator_y(lat)) * y_factor;
+    let a = (d_lat / 2.0).sin().powi(2) +
+        lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
 
-    PointCoord::new(x, y)
+    2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+/// Great-circle distance (in km).
+pub fn great_circle_distance(from: &GeoCoord, to: &GeoCoord) -> f64 {
+    central_angle(from, to) * EARTH_RADIUS_KM
+}
+
+fn normalize_lng(lng: f64) -> f64 {
+    let lng = (lng + 180.0).rem_euclid(360.0) - 180.0;
+    if lng == -180.0 { 180.0 } else { lng }
+}
+
+/// A point on the great circle between `from` and `to`,
+/// where `fraction` is 0.0 at `from` and 1.0 at `to`.
+pub fn intermediate_point(
+    from: &GeoCoord,
+    to: &GeoCoord,
+    fraction: f64,
+) -> GeoCoord {
+    let d = central_angle(from, to);
+
+    if d.abs() < f64::EPSILON {
+        return *from;
+    }
+
+    let lat1 = deg_to_rad(from.lat);
+    let lng1 = deg_to_rad(from.lng);
+    let lat2 = deg_to_rad(to.lat);
+    let lng2 = deg_to_rad(to.lng);
+
+    let a = ((1.0 - fraction) * d).sin() / d.sin();
+    let b = (fraction * d).sin() / d.sin();
+
+    let x = a * lat1.cos() * lng1.cos() + b * lat2.cos() * lng2.cos();
+    let y = a * lat1.cos() * lng1.sin() + b * lat2.cos() * lng2.sin();
+    let z = a * lat1.sin() + b * lat2.sin();
+
+    GeoCoord::new(
+        rad_to_deg(z.atan2((x * x + y * y).sqrt())),
+        normalize_lng(rad_to_deg(y.atan2(x))),
+    )
+}
+
+/// A point on the great circle from `from` toward `to`
+/// which is `distance` km away from `from`.
+pub fn intermediate_point_by_distance(
+    from: &GeoCoord,
+    to: &GeoCoord,
+    distance: f64,
+) -> GeoCoord {
+    let total = great_circle_distance(from, to);
+
+    if total < f64::EPSILON {
+        return *from;
+    }
+
+    intermediate_point(from, to, distance / total)
+}
+
+/// A point on the rhumb line (constant bearing)
+/// between `from` and `to`, where `fraction`
+/// is 0.0 at `from` and 1.0 at `to`.
+pub fn rhumb_intermediate_point(
+    from: &GeoCoord,
+    to: &GeoCoord,
+    fraction: f64,
+) -> GeoCoord {
+    let lat1 = deg_to_rad(from.lat);
+    let lat2 = deg_to_rad(to.lat);
+
+    // Take the shorter way around.
+    let mut d_lng = to.lng - from.lng;
+    if d_lng > 180.0 { d_lng -= 360.0; }
+    if d_lng < -180.0 { d_lng += 360.0; }
+
+    let lat = lat1 + (lat2 - lat1) * fraction;
+    let d_psi = mercator_y(lat2) - mercator_y(lat1);
+
+    let lng = if d_psi.abs() > 1e-12 {
+        from.lng + d_lng * (mercator_y(lat) - mercator_y(lat1)) / d_psi
+    } else {
+        from.lng + d_lng * fraction
+    };
+
+    GeoCoord::new(rad_to_deg(lat), normalize_lng(lng))
+}
+
+/// Samples `segments + 1` points along the great circle
+/// (including both ends).
+pub fn great_circle_points(
+    from: &GeoCoord,
+    to: &GeoCoord,
+    segments: usize,
+) -> Vec<GeoCoord> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| intermediate_point(from, to, i as f64 / segments as f64))
+        .collect()
+}
+
+/// Samples `segments + 1` points along the rhumb line
+/// (including both ends).
+pub fn rhumb_line_points(
+    from: &GeoCoord,
+    to: &GeoCoord,
+    segments: usize,
+) -> Vec<GeoCoord> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| rhumb_intermediate_point(from, to, i as f64 / segments as f64))
+        .collect()
 }
 
 // #[cfg(test)]
@@ -168,3 +499,163 @@ pub fn get_mercator_position(
 //         assert_eq!(res.longitude(), 10.0);
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn singapore() -> GeoCoord { GeoCoord::new(1.356, 103.987) }
+    fn taipei() -> GeoCoord { GeoCoord::new(25.08, 121.232) }
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() < tolerance, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn great_circle_distance_singapore_taipei() {
+        // Around 3,230 km.
+        assert_close(great_circle_distance(&singapore(), &taipei()), 3230.0, 15.0);
+    }
+
+    #[test]
+    fn intermediate_point_ends() {
+        let p0 = intermediate_point(&singapore(), &taipei(), 0.0);
+        let p1 = intermediate_point(&singapore(), &taipei(), 1.0);
+        assert_close(p0.lat, 1.356, 1e-9);
+        assert_close(p0.lng, 103.987, 1e-9);
+        assert_close(p1.lat, 25.08, 1e-9);
+        assert_close(p1.lng, 121.232, 1e-9);
+    }
+
+    #[test]
+    fn intermediate_point_by_distance_matches_fraction() {
+        let total = great_circle_distance(&singapore(), &taipei());
+        let a = intermediate_point_by_distance(&singapore(), &taipei(), total / 4.0);
+        let b = intermediate_point(&singapore(), &taipei(), 0.25);
+        assert_close(a.lat, b.lat, 1e-9);
+        assert_close(a.lng, b.lng, 1e-9);
+        assert_close(great_circle_distance(&singapore(), &a), total / 4.0, 1e-6);
+    }
+
+    #[test]
+    fn great_circle_bends_poleward_unlike_rhumb_line() {
+        let from = GeoCoord::new(50.0, -60.0);
+        let to = GeoCoord::new(50.0, 0.0);
+        let gc = intermediate_point(&from, &to, 0.5);
+        let rl = rhumb_intermediate_point(&from, &to, 0.5);
+        assert_close(gc.lat, 54.0, 0.1);
+        assert_close(rl.lat, 50.0, 1e-9);
+        assert_close(rl.lng, -30.0, 1e-9);
+    }
+
+    #[test]
+    fn sampled_points_include_both_ends() {
+        let points = great_circle_points(&singapore(), &taipei(), 8);
+        assert_eq!(points.len(), 9);
+        assert_close(points[8].lat, 25.08, 1e-9);
+        assert_eq!(rhumb_line_points(&singapore(), &taipei(), 0).len(), 2);
+    }
+
+    fn pacific() -> LatLngBounds {
+        LatLngBounds::new(60.0, -120.0, -10.0, 150.0)
+    }
+
+    #[test]
+    fn pacific_bounds_wrap_around() {
+        let bounds = pacific();
+        assert!(bounds.crosses_antimeridian());
+        assert_close(bounds.lng_span(), 90.0, 1e-9);
+        assert_close(bounds.center_lng(), -165.0, 1e-9);
+        assert!(bounds.contains(&GeoCoord::new(21.3, -157.9))); // Honolulu
+        assert!(bounds.contains(&GeoCoord::new(35.5, 179.0)));
+        assert!(!bounds.contains(&GeoCoord::new(1.356, 103.987))); // Singapore
+        assert!(!LatLngBounds::new(60.0, 150.0, -10.0, 90.0).crosses_antimeridian());
+    }
+
+    #[test]
+    fn projection_picks_closest_world_copy() {
+        let size = Size::new(900.0, 600.0);
+        let bounds = pacific();
+        let projection = WebMercatorProjection::new(&size, &bounds);
+        let x = |lng: f64| projection.project(&GeoCoord::new(0.0, lng)).x;
+
+        assert_close(x(150.0), 0.0, 1e-6);
+        assert_close(x(160.0), 100.0, 1e-6);
+        assert_close(x(180.0), 300.0, 1e-6);
+        assert_close(x(-180.0), 300.0, 1e-6);
+        assert_close(x(-170.0), 400.0, 1e-6);
+        assert_close(x(-120.0), 900.0, 1e-6);
+        // Just outside on either side stays next to the bounds.
+        assert_close(x(140.0), -100.0, 1e-6);
+        assert_close(x(-110.0), 1000.0, 1e-6);
+    }
+
+    #[test]
+    fn unwrap_path_removes_jumps() {
+        let path = vec![
+            GeoCoord::new(0.0, 170.0),
+            GeoCoord::new(0.0, 179.0),
+            GeoCoord::new(0.0, -179.0),
+            GeoCoord::new(0.0, -170.0),
+        ];
+        let lngs: Vec<f64> = unwrap_path(&path).iter().map(|c| c.lng).collect();
+        assert_eq!(lngs, vec![170.0, 179.0, 181.0, 190.0]);
+    }
+
+    #[test]
+    fn path_across_antimeridian_stays_continuous() {
+        let size = Size::new(900.0, 600.0);
+        let bounds = pacific();
+        let tokyo = GeoCoord::new(35.772, 140.393);
+        let san_francisco = GeoCoord::new(37.619, -122.375);
+        let path = great_circle_points(&tokyo, &san_francisco, 80);
+        let points = WebMercatorProjection::new(&size, &bounds).project_path(&path);
+
+        assert_eq!(points.len(), 81);
+        assert!(points.windows(2).all(|w| w[1].x > w[0].x));
+        assert!(points.windows(2).all(|w| (w[1].x - w[0].x) < 50.0));
+        // 10px per degree: Tokyo is just west of the bounds.
+        assert_close(points[0].x, -96.07, 1e-6);
+        assert_close(points[80].x, 876.25, 1e-6);
+    }
+
+    #[test]
+    fn web_mercator_round_trip() {
+        let size = Size::new(900.0, 600.0);
+        let bounds = pacific();
+        let projection = ProjectionKind::WebMercator.create(&size, &bounds);
+
+        for coord in [GeoCoord::new(21.3, -157.9), GeoCoord::new(35.5, 179.0), GeoCoord::new(-5.0, 155.0)] {
+            let back = projection.unproject(&projection.project(&coord));
+            assert_close(back.lat, coord.lat, 1e-9);
+            assert_close(back.lng, coord.lng, 1e-9);
+        }
+
+        let top_left = projection.unproject(&PointCoord::new(0.0, 0.0));
+        assert_close(top_left.lat, 60.0, 1e-9);
+        assert_close(top_left.lng, 150.0, 1e-9);
+    }
+
+    #[test]
+    fn projection_kind_from_name() {
+        assert_eq!(ProjectionKind::from_name("mercator"), Some(ProjectionKind::WebMercator));
+        assert_eq!(ProjectionKind::from_name("web_mercator"), Some(ProjectionKind::WebMercator));
+        assert_eq!(ProjectionKind::from_name("equirectangular"), Some(ProjectionKind::Equirectangular));
+        assert_eq!(ProjectionKind::from_name("robinson"), None);
+    }
+
+    #[test]
+    fn equirectangular_is_linear() {
+        let size = Size::new(900.0, 700.0);
+        let bounds = pacific();
+        let projection = EquirectangularProjection::new(&size, &bounds);
+
+        let p = projection.project(&GeoCoord::new(25.0, -170.0));
+        assert_close(p.x, 400.0, 1e-9);
+        assert_close(p.y, 350.0, 1e-9);
+
+        let back = projection.unproject(&PointCoord::new(300.0, 700.0));
+        assert_close(back.lat, -10.0, 1e-9);
+        assert_close(back.lng, 180.0, 1e-9);
+    }
+}
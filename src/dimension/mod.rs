@@ -1,6 +1,7 @@
 pub mod canvas;
 pub mod geo;
 pub mod point;
+pub mod spatial;
 pub mod window;
 
 use crate::utils::{
@@ -8,6 +8,23 @@ impl PointCoord {
     pub fn new(x: f64, y: f64) -> Self {
         PointCoord { x, y }
     }
+
+    pub fn distance(&self, other: &PointCoord) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    /// Distance to the line segment between `a` and `b`.
+    pub fn distance_to_segment(&self, a: &PointCoord, b: &PointCoord) -> f64 {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len2 = dx * dx + dy * dy;
+
+        if len2 == 0.0 {
+            return self.distance(a);
+        }
+
+        let t = (((self.x - a.x) * dx + (self.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+        self.distance(&PointCoord::new(a.x + t * dx, a.y + t * dy))
+    }
 }
 
 impl Default for PointCoord {
@@ -0,0 +1,330 @@
+/// A spatial index for anything having a coordinate
+/// (see `GeoCoordTrait`), so that geographic queries
+/// need not scan everything.
+///
+/// Coordinates are turned into points on the unit sphere
+/// (x, y, z), and kept in a k-d tree. Unlike latitudes and
+/// longitudes, they have no trouble at the antimeridian nor
+/// at the poles, and the straight (chord) distance between
+/// two points grows with the great-circle distance.
+/// For `within_bounds`, we also keep the items sorted
+/// by latitude (since bounds are not round).
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::dimension::geo::{
+    EARTH_RADIUS_KM,
+    GeoCoord,
+    GeoCoordTrait,
+    LatLngBounds,
+    great_circle_distance,
+};
+use crate::utils::deg_to_rad;
+
+type Point = [f64; 3];
+
+/// What queries return. `distance` is the great-circle
+/// distance (in km) from the coordinate queried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor<'a, T> {
+    pub item: &'a T,
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T: GeoCoordTrait> {
+    items: Vec<T>,
+    points: Vec<Point>,
+    // Indices into `items` arranged as an implicit k-d tree:
+    // for `tree[lo..hi]`, the median is at `(lo + hi) / 2`
+    // (split by x, y, z, x, ... as it goes deeper),
+    // and the smaller ones on the left.
+    tree: Vec<usize>,
+    // Indices into `items` sorted by latitude.
+    by_lat: Vec<usize>,
+}
+
+impl<T: GeoCoordTrait> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        SpatialIndex::new(vec![])
+    }
+}
+
+impl<T: GeoCoordTrait> SpatialIndex<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        let points: Vec<Point> = items.iter()
+            .map(|item| to_point(&item.get_coord()))
+            .collect();
+
+        let mut tree: Vec<usize> = (0..items.len()).collect();
+        build(&mut tree, &points, 0);
+
+        let mut by_lat: Vec<usize> = (0..items.len()).collect();
+        by_lat.sort_by(|&a, &b| items[a].lat().total_cmp(&items[b].lat()));
+
+        SpatialIndex {
+            items,
+            points,
+            tree,
+            by_lat,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// `k` nearest items from `coord`, the nearest first.
+    pub fn nearest(&self, coord: &GeoCoord, k: usize) -> Vec<Neighbor<'_, T>> {
+        if k == 0 {
+            return vec![];
+        }
+
+        let target: Point = to_point(coord);
+        // The farthest of the best `k` so far on the top.
+        let mut best: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        self.search(&target, 0, self.tree.len(), 0, &mut |index, dist2, best_dist2| {
+            if best.len() < k {
+                best.push(Candidate { dist2, index });
+            } else if dist2 < best_dist2 {
+                best.pop();
+                best.push(Candidate { dist2, index });
+            }
+            match best.peek() {
+                Some(worst) if best.len() == k => worst.dist2,
+                _ => f64::INFINITY,
+            }
+        }, f64::INFINITY);
+
+        let mut found: Vec<Candidate> = best.into_vec();
+        found.sort();
+        self.neighbors(coord, found)
+    }
+
+    /// Items within `km` (great-circle) from `coord`,
+    /// the nearest first.
+    pub fn within_radius(&self, coord: &GeoCoord, km: f64) -> Vec<Neighbor<'_, T>> {
+        if km < 0.0 {
+            return vec![];
+        }
+
+        let target: Point = to_point(coord);
+        let angle: f64 = (km / EARTH_RADIUS_KM).min(std::f64::consts::PI);
+        let chord: f64 = 2.0 * (angle / 2.0).sin();
+        // A little room for rounding errors.
+        let limit: f64 = chord * chord + 1e-12;
+
+        let mut found: Vec<Candidate> = vec![];
+
+        self.search(&target, 0, self.tree.len(), 0, &mut |index, dist2, _| {
+            if dist2 <= limit {
+                found.push(Candidate { dist2, index });
+            }
+            limit
+        }, limit);
+
+        found.sort();
+        self.neighbors(coord, found)
+    }
+
+    /// Items inside `bounds` (which may cross the antimeridian),
+    /// from the south to the north.
+    pub fn within_bounds(&self, bounds: &LatLngBounds) -> Vec<&T> {
+        let start: usize = self.by_lat
+            .partition_point(|&i| self.items[i].lat() < bounds.south);
+
+        self.by_lat[start..].iter()
+            .take_while(|&&i| self.items[i].lat() <= bounds.north)
+            .map(|&i| &self.items[i])
+            .filter(|item| bounds.contains(&item.get_coord()))
+            .collect()
+    }
+
+    // Visits the tree `tree[lo..hi]`. `visit` is called with
+    // each item (its index and squared chord distance) and
+    // the current limit, and returns the new limit (squared).
+    // Branches farther than the limit are skipped.
+    fn search(
+        &self,
+        target: &Point,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        visit: &mut dyn FnMut(usize, f64, f64) -> f64,
+        limit: f64,
+    ) -> f64 {
+        if lo >= hi {
+            return limit;
+        }
+
+        let mid: usize = (lo + hi) / 2;
+        let index: usize = self.tree[mid];
+        let point: &Point = &self.points[index];
+        let axis: usize = depth % 3;
+
+        let mut limit: f64 = visit(index, dist2(target, point), limit);
+
+        let diff: f64 = target[axis] - point[axis];
+        let (near, far) = if diff < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+
+        limit = self.search(target, near.0, near.1, depth + 1, visit, limit);
+
+        if diff * diff <= limit {
+            limit = self.search(target, far.0, far.1, depth + 1, visit, limit);
+        }
+
+        limit
+    }
+
+    fn neighbors(&self, coord: &GeoCoord, found: Vec<Candidate>) -> Vec<Neighbor<'_, T>> {
+        found.into_iter()
+            .map(|c| {
+                let item: &T = &self.items[c.index];
+                Neighbor {
+                    item,
+                    distance: great_circle_distance(coord, &item.get_coord()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    dist2: f64,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Ties are broken by the index so that results are stable.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dist2.total_cmp(&other.dist2)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+fn build(tree: &mut [usize], points: &[Point], depth: usize) {
+    if tree.len() <= 1 {
+        return;
+    }
+
+    let axis: usize = depth % 3;
+    let mid: usize = tree.len() / 2;
+
+    tree.select_nth_unstable_by(mid, |&a, &b| {
+        points[a][axis].total_cmp(&points[b][axis])
+    });
+
+    let (left, right) = tree.split_at_mut(mid);
+    build(left, points, depth + 1);
+    build(&mut right[1..], points, depth + 1);
+}
+
+fn to_point(coord: &GeoCoord) -> Point {
+    let lat: f64 = deg_to_rad(coord.lat);
+    let lng: f64 = deg_to_rad(coord.lng);
+    [lat.cos() * lng.cos(), lat.cos() * lng.sin(), lat.sin()]
+}
+
+fn dist2(a: &Point, b: &Point) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Place(&'static str, f64, f64);
+
+    impl GeoCoordTrait for Place {
+        fn get_coord(&self) -> GeoCoord { GeoCoord::new(self.1, self.2) }
+    }
+
+    fn index() -> SpatialIndex<Place> {
+        SpatialIndex::new(vec![
+            Place("WSSS", 1.356, 103.987),
+            Place("WMKK", 2.746, 101.710),
+            Place("VHHH", 22.309, 113.914),
+            Place("RJTT", 35.552, 139.779),
+            Place("PHNL", 21.318, -157.922),
+            Place("NFFN", -17.755, 177.443),
+            Place("NZAA", -37.008, 174.792),
+            Place("KSFO", 37.619, -122.375),
+            Place("EGLL", 51.477, -0.461),
+        ])
+    }
+
+    fn names<T>(found: &[Neighbor<'_, Place>], f: impl Fn(&Neighbor<'_, Place>) -> T) -> Vec<T> {
+        found.iter().map(f).collect()
+    }
+
+    #[test]
+    fn nearest_matches_linear_scan() {
+        let index = index();
+        let here = GeoCoord::new(10.0, 110.0);
+
+        let found = index.nearest(&here, 3);
+        assert_eq!(names(&found, |n| n.item.0), vec!["WSSS", "WMKK", "VHHH"]);
+
+        let expected = great_circle_distance(&here, &GeoCoord::new(1.356, 103.987));
+        assert!((found[0].distance - expected).abs() < 1e-6);
+
+        assert_eq!(index.nearest(&here, 100).len(), 9);
+        assert!(index.nearest(&here, 0).is_empty());
+    }
+
+    #[test]
+    fn nearest_across_antimeridian() {
+        // Just east of 180 is next to Fiji (at 177E).
+        let index = index();
+        let found = index.nearest(&GeoCoord::new(-17.0, -179.0), 1);
+        assert_eq!(found[0].item.0, "NFFN");
+    }
+
+    #[test]
+    fn within_radius_is_sorted() {
+        let index = index();
+        let found = index.within_radius(&GeoCoord::new(1.356, 103.987), 400.0);
+        assert_eq!(names(&found, |n| n.item.0), vec!["WSSS", "WMKK"]);
+        assert_eq!(found[0].distance, 0.0);
+
+        let found = index.within_radius(&GeoCoord::new(0.0, 0.0), 30000.0);
+        assert_eq!(found.len(), 9);
+    }
+
+    #[test]
+    fn within_bounds_crossing_antimeridian() {
+        let pacific = LatLngBounds::new(30.0, -150.0, -40.0, 170.0);
+        let index = index();
+        let found = index.within_bounds(&pacific);
+        assert_eq!(found.iter().map(|p| p.0).collect::<Vec<_>>(), vec!["NZAA", "NFFN", "PHNL"]);
+
+        let asia = LatLngBounds::new(40.0, 140.0, 0.0, 100.0);
+        assert_eq!(index.within_bounds(&asia).len(), 4);
+    }
+}
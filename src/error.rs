@@ -0,0 +1,157 @@
+/// Errors which may occur while fetching arrivals/departures
+/// and turning them into the data we want for the app.
+/// When they finally reach JS (e.g. when the _Promise_
+/// returned from `App::prepare` rejects), they are
+/// converted into JS `Error` objects carrying `kind`
+/// and whatever details each variant has.
+
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FlightPackError {
+    /// The request never got a response.
+    Network { url: String, message: String },
+
+    /// The response came back, but not with `2xx`.
+    HttpStatus { url: String, status: u16 },
+
+    /// JSON did not look like what we expected.
+    /// `field` is the path to the offending field
+    /// (e.g. `arrivals[3].origin.code_icao`).
+    Decode { source: String, field: String, message: String },
+
+    /// JSON could not be made out of our own data.
+    Encode { message: String },
+
+    /// The airport is not in our Airport Database.
+    UnknownAirport { icao: String },
+
+    /// ICAO/IATA codes given for `prepare` which are
+    /// not in our Airport Database.
+    UnresolvedAirports { codes: Vec<String> },
+
+    /// No bundled (or in-memory) page for the path.
+    MissingFixture { path: String },
+
+    /// The filter expression did not parse (or type check).
+    /// `position` is the character offset in `expression`.
+    Expression { expression: String, message: String, position: usize },
+}
+
+impl fmt::Display for FlightPackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlightPackError::Network { url, message } => {
+                write!(f, "Network failure for {}: {}", url, message)
+            },
+            FlightPackError::HttpStatus { url, status } => {
+                write!(f, "HTTP {} for {}", status, url)
+            },
+            FlightPackError::Decode { source, field, message } => {
+                write!(f, "Failed to decode \"{}\" in {}: {}", field, source, message)
+            },
+            FlightPackError::Encode { message } => {
+                write!(f, "Failed to encode: {}", message)
+            },
+            FlightPackError::UnknownAirport { icao } => {
+                write!(f, "Unknown airport: {}", icao)
+            },
+            FlightPackError::UnresolvedAirports { codes } => {
+                write!(f, "Unknown airport codes: {}", codes.join(", "))
+            },
+            FlightPackError::MissingFixture { path } => {
+                write!(f, "No fixture for: {}", path)
+            },
+            FlightPackError::Expression { message, position, .. } => {
+                write!(f, "Invalid filter expression at {}: {}", position, message)
+            },
+        }
+    }
+}
+
+impl std::error::Error for FlightPackError {}
+
+/// For JS, we want an `Error` (so that it has `message`
+/// and a stack), but with `kind` and details attached.
+impl From<FlightPackError> for JsValue {
+    fn from(err: FlightPackError) -> Self {
+        let error = js_sys::Error::new(&err.to_string());
+        error.set_name("FlightPackError");
+
+        if let Ok(detail) = encode_js_value(&err) {
+            js_sys::Object::assign(&error, detail.unchecked_ref());
+        }
+
+        error.into()
+    }
+}
+
+/// Deserialize `json`, and when failed, tell which field
+/// it was. `source` is where `json` came from
+/// (usually, the URL or the fixture path).
+pub fn decode_json<T: DeserializeOwned>(
+    json: &str,
+    source: &str,
+) -> Result<T, FlightPackError> {
+    let de = &mut serde_json::Deserializer::from_str(json);
+
+    serde_path_to_error::deserialize(de).map_err(|err| {
+        FlightPackError::Decode {
+            source: source.into(),
+            field: err.path().to_string(),
+            message: err.inner().to_string(),
+        }
+    })
+}
+
+/// Same as `decode_json`, but for what JS gave us
+/// (`JSON.stringify` first, then deserialize).
+pub fn decode_js_value<T: DeserializeOwned>(
+    value: &JsValue,
+    source: &str,
+) -> Result<T, FlightPackError> {
+    let json: String = js_sys::JSON::stringify(value)
+        .map(String::from)
+        .unwrap_or_default();
+
+    decode_json::<T>(&json, source)
+}
+
+/// Turns our data into a JS value for JS to receive
+/// (`serde_json`, then `JSON.parse`).
+pub fn encode_js_value<T: Serialize>(value: &T) -> Result<JsValue, FlightPackError> {
+    let json: String = serde_json::to_string(value)
+        .map_err(|err| FlightPackError::Encode {
+            message: err.to_string(),
+        })?;
+
+    js_sys::JSON::parse(&json)
+        .map_err(|err| FlightPackError::Encode {
+            message: err.as_string().unwrap_or_else(|| "JSON.parse failed".into()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aviation::arrival::AeroArrivalsRawData;
+
+    #[test]
+    fn decode_json_reports_field_path() {
+        let json = r#"{ "links": null, "num_pages": 1, "arrivals": [{ "ident": 1 }] }"#;
+        let err = decode_json::<AeroArrivalsRawData>(json, "/airports/WSSS/flights/arrivals")
+            .unwrap_err();
+
+        match err {
+            FlightPackError::Decode { source, field, .. } => {
+                assert_eq!(source, "/airports/WSSS/flights/arrivals");
+                assert_eq!(field, "arrivals[0].ident");
+            },
+            _ => panic!("Expected a decode error"),
+        }
+    }
+}
@@ -6,8 +6,10 @@ extern crate load_dotenv;
 
 pub mod app;
 pub mod aviation;
+pub mod clock;
 pub mod constants;
 pub mod dimension;
+pub mod error;
 pub mod manager;
 pub mod proxy;
 pub mod request;
@@ -2,108 +2,192 @@
 ///
 /// (1) Managing `AirportGraphics`,
 /// (2) Convert `airports` fed by JS, and
-/// (3) Fetching arrival/departure info from FlightAware API.
+/// (3) Fetching arrival/departure info from `FlightDataSource`.
 
+use chrono::{DateTime, Utc};
 use std::cell::RefCell;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use wasm_bindgen::JsValue;
-use web_sys::{
-    console,
-    CanvasRenderingContext2d,
-};
+use web_sys::CanvasRenderingContext2d;
 
 use crate::aviation::airport::{
     TargetAirportRawData,
     Airport,
+    decode_target_airports,
+    resolve_target_airports,
 };
 use crate::aviation::arrival::{
-    AeroArrivalsRawData,
+    AeroArrivalsActualRawData,
+    AeroPagedRawData,
     AirportArrival,
-    fetch_arrivals,
+    collapse_codeshares,
+};
+use crate::aviation::delay::{DelayLegendEntry, DelayThresholds};
+use crate::aviation::filter::FlightFilter;
+use crate::aviation::flight::{Flight, FlightColorBy, FlightPathKind};
+use crate::aviation::pick::{PickResult, pick};
+use crate::aviation::reference::lookup_airport_database;
+use crate::aviation::stats::FlightStats;
+use crate::aviation::status::StatusOptions;
+use crate::aviation::source::{
+    FlightDataSource,
+    FixtureSource,
+};
+use crate::clock::Clock;
+use crate::constants::{
+    AERO_API_MAX_PAGES,
+    STATS_MAX_ROUTES,
 };
-use crate::aviation::flight::Flight;
 use crate::dimension::canvas::Canvas;
 use crate::dimension::geo::{
     LatLngBounds,
     // GeoCoordTrait,
     GeoCoord,
+    Projection,
+    ProjectionKind,
+    WebMercatorProjection,
 };
+use crate::dimension::point::PointCoord;
 use crate::dimension::Size;
+use crate::error::{FlightPackError, decode_js_value};
 
-type ArrivalsResponse = Result<AeroArrivalsRawData, String>;
-type ArrivalsResponsePinBox = Pin<Box<dyn Future<Output = ArrivalsResponse>>>;
+type FlightsResponse = Result<Vec<AeroArrivalsActualRawData>, FlightPackError>;
+type FlightsResponsePinBox = Pin<Box<dyn Future<Output = FlightsResponse>>>;
 
 #[derive(Debug)]
 pub struct Manager {
+    source: Rc<dyn FlightDataSource>,
     canvas: Size,
     bounds: LatLngBounds,
+    projection_kind: ProjectionKind,
+    projection: Box<dyn Projection>,
     airports: Vec<Airport>,
     airport_icaos: Vec<String>,
     arrivals: Vec<AirportArrival>,
     flights: Vec<Flight>,
+    max_pages: usize,
+    needs_update: bool,
+    selected: Option<String>,
+    delay_thresholds: DelayThresholds,
+    status_options: StatusOptions,
+    color_by: FlightColorBy,
+    filter: FlightFilter,
+    stats: FlightStats,
 }
 
 impl Default for Manager {
     fn default() -> Self {
-        Self::new()
+        Self::new(Rc::new(FixtureSource::new()))
     }
 }
 
 impl Manager {
-    pub fn new() -> Self {
+    pub fn new(source: Rc<dyn FlightDataSource>) -> Self {
         Manager {
+            source,
             canvas: Size::new(1.0, 1.0), // !!!
             bounds: LatLngBounds::default(),
+            projection_kind: ProjectionKind::default(),
+            projection: Box::new(WebMercatorProjection::default()),
             airports: vec![],
             airport_icaos: vec![],
             arrivals: vec![],
             flights: vec![],
+            max_pages: AERO_API_MAX_PAGES,
+            needs_update: false,
+            selected: None,
+            delay_thresholds: DelayThresholds::default(),
+            status_options: StatusOptions::default(),
+            color_by: FlightColorBy::default(),
+            filter: FlightFilter::default(),
+            stats: FlightStats::default(),
         }
     }
 
+    /// How many pages to follow for each airport
+    /// when fetching arrivals/departures.
+    pub fn set_max_pages(&mut self, max_pages: usize) {
+        self.max_pages = max_pages.max(1);
+    }
+
     /// JS is calling `App::prepare`, and is calling `Proxy::prepare`,
     /// and this is called. Following tasks are carried out:
     ///
     /// (1) Convert `airports` into Rust data.
     /// (2) For `airports`, fetch arrival/departure information.
+    ///
+    /// Any failure on the way is returned as is,
+    /// and `App::prepare` will reject with it.
+    /// `now` is the time given by `Clock`.
     pub async fn prepare(
         &mut self,
         ctx: &CanvasRenderingContext2d,
         airports: JsValue,
-    ) -> Result<JsValue, JsValue> {
-        console::log_1(&(
-            "[manager] ++++ prepare()".into()
-        ));
-        self.set_airports(airports);
+        now: DateTime<Utc>,
+    ) -> Result<JsValue, FlightPackError> {
+        log("[manager] ++++ prepare()");
+        let airports: Vec<TargetAirportRawData> = resolve_target_airports(
+            decode_target_airports(
+                decode_js_value::<Vec<serde_json::Value>>(&airports, "airports")?,
+                "airports",
+            )?
+        )?;
+        self.load(airports, now).await?;
+        // Calculate for texts' width prior to the actual render.
         self.set_text_width(ctx);
-        self.set_arrivals().await;
-        self.set_flights();
         self.get_coords_from_arrivals()
     }
 
-    /// Convert the JS given `airports` into Rust data.
-    /// Also, calculate for texts' width prior to the actual render.
-    pub fn set_airports(&mut self, airports: JsValue) {
-        console::log_1(&(
-            "[manager] ++++ set_airports()".into()
-        ));
-        let airports: Result<Vec<TargetAirportRawData>, serde_json::Error> =
-            airports.into_serde();
+    /// What `prepare` does once `airports` are resolved.
+    /// Airports and arrivals are replaced only when
+    /// all of them succeeded.
+    async fn load(
+        &mut self,
+        airports: Vec<TargetAirportRawData>,
+        now: DateTime<Utc>,
+    ) -> Result<(), FlightPackError> {
+        let previous = self.set_airports(airports)?;
+        if let Err(err) = self.set_arrivals(now).await {
+            // Keep airports matching the flights we still have.
+            (self.airports, self.airport_icaos) = previous;
+            return Err(err);
+        }
+        self.set_flights();
+        // New flights have no paths projected yet.
+        self.needs_update = true;
+        Ok(())
+    }
 
-        let airports: Vec<TargetAirportRawData> = airports
-            .map_or_else(
-                |err| panic!("[manager] (airports) {:?}", err),
-                |airport| airport,
-            );
+    /// Convert the resolved `airports` into `Airport`s.
+    /// Nothing changes when failed, and the previous airports
+    /// (with their ICAO codes) are returned when succeeded.
+    fn set_airports(
+        &mut self,
+        airports: Vec<TargetAirportRawData>,
+    ) -> Result<(Vec<Airport>, Vec<String>), FlightPackError> {
+        log("[manager] ++++ set_airports()");
+        if let Some(raw) = airports.iter().find(|raw| {
+            lookup_airport_database(&raw.icao).is_none()
+        }) {
+            return Err(FlightPackError::UnknownAirport {
+                icao: raw.icao.clone(),
+            });
+        }
 
-        self.airports = airports.iter()
-            .map(|raw| {
-                self.airport_icaos.push(raw.icao.clone());
-                Airport::new(raw.clone())
-            })
-            .collect::<Vec<Airport>>();
+        // Replacing the whole (for when `prepare` is called again).
+        let icaos: Vec<String> = airports.iter()
+            .map(|raw| raw.icao.clone())
+            .collect();
+        let airports: Vec<Airport> = airports.into_iter()
+            .map(Airport::new)
+            .collect();
+
+        Ok((
+            std::mem::replace(&mut self.airports, airports),
+            std::mem::replace(&mut self.airport_icaos, icaos),
+        ))
     }
 
     /// Runs a set of tests to check the text width for airport names.
@@ -114,93 +198,142 @@ impl Manager {
     }
 
     /// For `airports` (which is JS given), we will fetch
-    /// arrivals/departures from FlightAware API.
-    async fn set_arrivals(&mut self) {
-        console::log_1(&(
-            "[manager] ++++ set_arrivals()".into()
-        ));
-        let mut request: Vec<Box<dyn Fn() -> ArrivalsResponsePinBox>> = vec![];
+    /// arrivals/departures from `FlightDataSource`.
+    /// The same flight may appear both in the arrivals
+    /// of one airport and in the departures of another,
+    /// so we merge them deduplicating by `fa_flight_id`.
+    /// The previous arrivals are kept when failed.
+    async fn set_arrivals(
+        &mut self,
+        now: DateTime<Utc>,
+    ) -> Result<(), FlightPackError> {
+        log("[manager] ++++ set_arrivals()");
+        let mut request: Vec<Box<dyn Fn() -> FlightsResponsePinBox>> = vec![];
+        let max_pages: usize = self.max_pages;
+        let recorded: bool = self.source.is_recorded();
+        let source: &Rc<dyn FlightDataSource> = &self.source;
 
         // In the next line, no actual fetchings are executed,
         // but just constructing Rust's `Future`s.
         // They will later be executed using `await`.
         self.airports.iter().for_each(|p| {
             request.push(
-                Box::new(move || Box::pin(
-                    fetch_arrivals(
-                        p.icao.clone()
-                    )
-                ))
+                Box::new(move || {
+                    let icao = p.icao.clone();
+                    let source = source.clone();
+                    Box::pin(async move {
+                        source.arrivals(icao.clone(), max_pages).await
+                            .map(|res| {
+                                if res.has_more() {
+                                    warn_page_limit("arrivals", &icao, max_pages);
+                                }
+                                res.arrivals
+                            })
+                    })
+                })
+            );
+            request.push(
+                Box::new(move || {
+                    let icao = p.icao.clone();
+                    let source = source.clone();
+                    Box::pin(async move {
+                        source.departures(icao.clone(), max_pages).await
+                            .map(|res| {
+                                if res.has_more() {
+                                    warn_page_limit("departures", &icao, max_pages);
+                                }
+                                res.departures
+                            })
+                    })
+                })
             );
         });
 
         // Let's fetch using `await`!
-        // (we have `join_all` so that we could add others in the future)
-        let response: Vec<Result<AeroArrivalsRawData, String>> =
+        let response: Vec<FlightsResponse> =
             futures::future::join_all(
                 request.into_iter().map(|r| r())
             ).await;
 
-        // Ignore errors and OK all.
-        let rawdata: Vec<AeroArrivalsRawData> = response.iter()
-            .filter_map(|res| res.clone().ok())
-            .collect();
+        // Fails if any of the airports failed.
+        let rawdata: Vec<Vec<AeroArrivalsActualRawData>> = response
+            .into_iter()
+            .collect::<Result<Vec<_>, FlightPackError>>()?;
 
-        // Set only the arrivals/departures that are valid.
-        rawdata.iter().for_each(|airport| {
-            airport.arrivals.iter().for_each(|arrival| {
-                if let Some(res) = arrival.extract(&self.airport_icaos) {
-                    self.arrivals.push(res);
-                }
-            });
-        });
+        // Set only the arrivals/departures that are valid,
+        // and only once for each `fa_flight_id`
+        // (with codeshares collected from all the records).
+        self.arrivals = collapse_codeshares(rawdata.into_iter().flatten())
+            .iter()
+            .filter_map(|flight| flight.extract(&self.airport_icaos, recorded, now))
+            .collect();
 
         self.arrivals.iter().enumerate().for_each(|(i, arrival)| {
-            console::log_1(&(
-                "[manager] ---------------".into()
-            ));
-            console::log_1(&(
-                format!(
-                    "[manager] [{}] (departure) {} ({}, {})",
-                    i,
-                    arrival.orig_airport.name,
-                    arrival.orig_airport.city,
-                    arrival.orig_airport.country,
-                ).into()
+            log("[manager] ---------------");
+            log(&format!(
+                "[manager] [{}] (departure) {} ({}, {})",
+                i,
+                arrival.orig_airport.name,
+                arrival.orig_airport.city,
+                arrival.orig_airport.country,
             ));
 
-            console::log_1(&(
-                format!(
-                    "[manager] [{}] (arrival) {} ({}, {})",
-                    i,
-                    arrival.dest_airport.name,
-                    arrival.dest_airport.city,
-                    arrival.dest_airport.country,
-                ).into()
+            log(&format!(
+                "[manager] [{}] (arrival) {} ({}, {})",
+                i,
+                arrival.dest_airport.name,
+                arrival.dest_airport.city,
+                arrival.dest_airport.country,
             ));
         });
 
-        console::log_1(&(
-            format!(
-                "[manager] Total Arrivals: {}",
-                self.arrivals.len()
-            ).into()
+        log(&format!(
+            "[manager] Total Arrivals: {}",
+            self.arrivals.len()
         ));
+
+        Ok(())
     }
 
     fn set_flights(&mut self) {
-        console::log_1(&(
-            "[manager] ++++ set_flights()".into()
-        ));
+        log("[manager] ++++ set_flights()");
+        self.flights.clear();
         self.arrivals.clone().iter()
             .for_each(|arrival| {
-                self.flights.push(
-                    Flight::new(arrival.clone())
-                );
+                let mut flight = Flight::new(arrival.clone());
+                flight.set_delay_bucket(&self.delay_thresholds);
+                self.flights.push(flight);
             });
+        self.set_visibility();
+        self.set_stats();
+    }
+
+    // Whenever flights are (re)set.
+    fn set_stats(&mut self) {
+        self.stats = FlightStats::new(
+            &self.flights,
+            &self.airport_icaos,
+            STATS_MAX_ROUTES,
+        );
+    }
+
+    pub fn stats(&self) -> FlightStats {
+        self.stats.clone()
+    }
+
+    // Flights not meeting `status_options` or `filter`
+    // are neither drawn nor picked. Since statuses change
+    // as time goes, this runs every frame in `update()`.
+    fn set_visibility(&mut self) {
+        let options: &StatusOptions = &self.status_options;
+        let filter: &FlightFilter = &self.filter;
+
+        self.flights.iter_mut().for_each(|f| {
+            f.visible = options.includes(f.status) && filter.matches(f);
+        });
     }
 
-    fn _get_coords_from_airports(&self) -> Result<JsValue, JsValue> {
+    fn _get_coords_from_airports(&self) -> Result<JsValue, FlightPackError> {
         let coords: Vec<GeoCoord> =
             self.airports
             .iter()
@@ -209,12 +342,12 @@ impl Manager {
 
         serde_json::to_string(&coords)
             .map(JsValue::from)
-            .map_err(|_| JsValue::from(
-                "Failed to serialize arrivals data".to_string()
-            ))
+            .map_err(|err| FlightPackError::Encode {
+                message: err.to_string(),
+            })
     }
 
-    fn get_coords_from_arrivals(&self) -> Result<JsValue, JsValue> {
+    fn get_coords_from_arrivals(&self) -> Result<JsValue, FlightPackError> {
         let mut coords: Vec<GeoCoord> = vec![];
 
         self.arrivals.iter().for_each(|arrival| {
@@ -224,17 +357,115 @@ impl Manager {
 
         serde_json::to_string(&coords)
             .map(JsValue::from)
-            .map_err(|_| JsValue::from(
-                "Failed to serialize arrivals data".to_string()
-            ))
+            .map_err(|err| FlightPackError::Encode {
+                message: err.to_string(),
+            })
+    }
+
+    /// Draw `flight_id` (or all the flights when `None`)
+    /// along either great-circle or rhumb line.
+    pub fn set_path_kind(
+        &mut self,
+        flight_id: Option<&str>,
+        kind: FlightPathKind,
+    ) {
+        self.flights.iter_mut()
+            .filter(|f| flight_id.is_none_or(|id| f.id == id))
+            .for_each(|f| f.set_path_kind(kind));
+
+        self.needs_update = true;
+    }
+
+    /// The flight or the airport drawn nearest to `point`
+    /// (in canvas pixels) within `tolerance`.
+    pub fn pick(
+        &self,
+        point: &PointCoord,
+        tolerance: f64,
+    ) -> Option<PickResult> {
+        pick(&self.flights, &self.airports, point, tolerance)
+    }
+
+    /// Thresholds (in minutes) for coloring flights by delays.
+    /// Fields not given by JS keep the defaults,
+    /// and invalid ones are rejected (see `DelayThresholds::validate`).
+    pub fn set_delay_thresholds(&mut self, thresholds: JsValue) -> Result<(), FlightPackError> {
+        let thresholds = decode_js_value::<DelayThresholds>(&thresholds, "delay_thresholds")?;
+        thresholds.validate("delay_thresholds")?;
+        self.delay_thresholds = thresholds;
+
+        let thresholds: &DelayThresholds = &self.delay_thresholds;
+        self.flights.iter_mut().for_each(|f| {
+            f.set_delay_bucket(thresholds);
+        });
+
+        Ok(())
+    }
+
+    pub fn delay_legend(&self) -> Vec<DelayLegendEntry> {
+        self.delay_thresholds.legend()
+    }
+
+    /// Whether to show cancelled/diverted flights (and how).
+    /// Fields not given by JS keep the defaults.
+    pub fn set_status_options(&mut self, options: JsValue) -> Result<(), FlightPackError> {
+        self.status_options =
+            decode_js_value::<StatusOptions>(&options, "status_options")?;
+        self.set_visibility();
+
+        Ok(())
+    }
+
+    /// Only flights meeting `filter` are shown
+    /// (see `FlightFilter` for what it may have).
+    pub fn set_filter(&mut self, filter: JsValue) -> Result<(), FlightPackError> {
+        let mut filter = decode_js_value::<FlightFilter>(&filter, "filter")?;
+
+        filter.compile().map_err(|err| FlightPackError::Expression {
+            expression: filter.expression.clone().unwrap_or_default(),
+            message: err.message,
+            position: err.position,
+        })?;
+
+        self.filter = filter;
+        self.set_visibility();
+        Ok(())
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter = FlightFilter::default();
+        self.set_visibility();
+    }
+
+    pub fn set_color_by(&mut self, color_by: FlightColorBy) {
+        self.color_by = color_by;
+    }
+
+    /// Selected flight (`fa_flight_id`) gets a tooltip.
+    /// `None` to unselect.
+    pub fn select(&mut self, flight_id: Option<String>) {
+        self.selected = flight_id;
+    }
+
+    /// Projection currently in use. Since it is rebuilt
+    /// whenever canvas or bounds change, it always
+    /// matches what is drawn on the canvas.
+    pub fn projection(&self) -> &dyn Projection {
+        self.projection.as_ref()
+    }
+
+    pub fn set_projection_kind(&mut self, kind: ProjectionKind) {
+        self.projection_kind = kind;
+        self.needs_update = true;
     }
 
     fn is_update_needed(
         &self,
-        canvas: &Canvas,
+        canvas: &Size,
         bounds: &LatLngBounds,
     ) -> bool {
-        canvas.width != self.canvas.width ||
+        self.needs_update ||
+            canvas.width != self.canvas.width ||
             canvas.height != self.canvas.height ||
             bounds.north != self.bounds.north ||
             bounds.east != self.bounds.east ||
@@ -242,7 +473,7 @@ impl Manager {
             bounds.west != self.bounds.west
     }
 
-    fn set_canvas(&mut self, canvas: &Canvas) {
+    fn set_canvas(&mut self, canvas: &Size) {
         self.canvas.width = canvas.width;
         self.canvas.height = canvas.height;
     }
@@ -259,29 +490,50 @@ impl Manager {
     // information for `canvas` or `bounds` changes,
     // we will be updating its own, and will run
     // `AirportGraphics::update()` for each airport.
+    // Flight positions, however, move every frame
+    // against the time given by `Clock`.
     pub async fn update(
         &mut self,
         canvas: Rc<RefCell<Canvas>>,
         bounds: Rc<RefCell<LatLngBounds>>,
+        clock: Rc<RefCell<Clock>>,
     ) {
         let clone = canvas.clone();
-        let canvas = canvas.borrow();
+        let canvas: Size = canvas.borrow().size();
         let bounds = bounds.borrow();
+        let now: DateTime<Utc> = clock.borrow().now();
 
-        if self.is_update_needed(&canvas, &bounds) {
-            self.set_canvas(&canvas);
-            self.set_bounds(&bounds);
+        self.project(&canvas, &bounds);
 
-            self.airports.iter_mut().for_each(|p| {
-                p.update(&canvas, &bounds);
-            });
+        self.flights.iter_mut().for_each(|f| {
+            f.advance(now);
+        });
+        self.set_visibility();
 
-            self.flights.iter_mut().for_each(|f| {
-                f.update(&canvas, &bounds);
-            });
+        self.draw(&clone.borrow().ctx);
+    }
+
+    // Projects airports and flights again
+    // only when something has changed.
+    fn project(&mut self, canvas: &Size, bounds: &LatLngBounds) {
+        if !self.is_update_needed(canvas, bounds) {
+            return;
         }
 
-        self.draw(&clone.borrow().ctx);
+        self.needs_update = false;
+        self.set_canvas(canvas);
+        self.set_bounds(bounds);
+        self.projection = self.projection_kind.create(&self.canvas, &self.bounds);
+
+        let projection: &dyn Projection = self.projection.as_ref();
+
+        self.airports.iter_mut().for_each(|p| {
+            p.update(canvas, projection);
+        });
+
+        self.flights.iter_mut().for_each(|f| {
+            f.update(projection);
+        });
     }
 
     fn draw(&self, ctx: &CanvasRenderingContext2d) {
@@ -290,8 +542,84 @@ impl Manager {
             p.draw(ctx);
         });
         self.flights.iter().for_each(|f| {
-            f.draw(ctx);
+            f.draw(ctx, &self.status_options, self.color_by);
         });
+        if let Some(id) = self.selected.as_deref() {
+            self.flights.iter()
+                .filter(|f| f.id == id && f.visible)
+                .for_each(|f| f.draw_tooltip(ctx, &self.canvas));
+        }
         ctx.restore();
     }
 }
+
+/// `console` is there only in browsers
+/// (calling it from native tests aborts).
+fn log(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&message.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = message;
+}
+
+/// Let us know when an airport had more pages
+/// than we were willing to follow.
+fn warn_page_limit(kind: &str, icao: &str, max_pages: usize) {
+    web_sys::console::warn_1(&(
+        format!(
+            "[manager] Reached the page limit ({}) for {} at {}",
+            max_pages,
+            kind,
+            icao,
+        ).into()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use futures::executor::block_on;
+
+    use crate::aviation::airport::TargetAirportSpec;
+    use crate::aviation::source::MemorySource;
+
+    // Pages of `FixtureSource` for `icaos`, served from memory.
+    fn source(icaos: &[&str]) -> MemorySource {
+        let fixture = FixtureSource::new();
+        let mut source = MemorySource::new();
+
+        for icao in icaos {
+            source.insert_arrivals(icao, &block_on(fixture.arrivals(icao.to_string(), 1)).unwrap());
+            source.insert_departures(icao, &block_on(fixture.departures(icao.to_string(), 1)).unwrap());
+        }
+        source
+    }
+
+    fn airports(codes: &[&str]) -> Vec<TargetAirportRawData> {
+        resolve_target_airports(
+            codes.iter().map(|code| TargetAirportSpec::Code(code.to_string())).collect()
+        ).unwrap()
+    }
+
+    #[test]
+    fn prepare_again_projects_new_flights() {
+        let mut manager = Manager::new(Rc::new(source(&["VHHH", "VVTS", "RPLL"])));
+        let now = Utc.with_ymd_and_hms(2022, 8, 24, 8, 0, 0).unwrap();
+        let canvas = Size::new(900.0, 600.0);
+        let bounds = LatLngBounds::new(30.0, 130.0, 0.0, 100.0);
+
+        block_on(manager.load(airports(&["HKG", "SGN"]), now)).unwrap();
+        manager.project(&canvas, &bounds);
+        assert!(!manager.flights.is_empty());
+        assert!(manager.flights.iter().all(|f| !f.path_points.is_empty()));
+
+        // Neither canvas nor bounds changed,
+        // but the new flights still need their paths.
+        block_on(manager.load(airports(&["HKG", "MNL"]), now)).unwrap();
+        manager.project(&canvas, &bounds);
+        assert!(!manager.flights.is_empty());
+        assert!(manager.flights.iter().all(|f| !f.path_points.is_empty()));
+        assert!(manager.flights.iter().all(|f| f.dest_airport.icao == "VHHH" || f.dest_airport.icao == "RPLL"));
+    }
+}
@@ -32,6 +32,7 @@
 ///   }
 /// }
 /// ```
+use chrono::{DateTime, Utc};
 use std::cell::RefCell;
 use std::future::Future;
 use std::pin::Pin;
@@ -43,10 +44,18 @@ use web_sys::{
     HtmlCanvasElement,
 };
 
+use crate::aviation::delay::DelayLegendEntry;
+use crate::aviation::flight::{FlightColorBy, FlightPathKind};
+use crate::aviation::pick::PickResult;
+use crate::aviation::source::FlightDataSource;
+use crate::aviation::stats::FlightStats;
+use crate::clock::Clock;
 use crate::manager::Manager;
 use crate::dimension::canvas::Canvas;
-use crate::dimension::geo::LatLngBounds;
+use crate::dimension::geo::{GeoCoord, LatLngBounds, ProjectionKind};
+use crate::dimension::point::PointCoord;
 use crate::dimension::window::Window;
+use crate::error::FlightPackError;
 use crate::utils::{
     get_ctx,
     request_animation_frame_future,
@@ -58,23 +67,29 @@ pub struct Proxy {
     pub window: Rc<RefCell<Window>>,
     pub canvas: Rc<RefCell<Canvas>>,
     pub bounds: Rc<RefCell<LatLngBounds>>,
+    pub clock: Rc<RefCell<Clock>>,
     pub manager: Rc<RefCell<Manager>>,
 }
 
 #[allow(clippy::await_holding_refcell_ref)]
 impl Proxy {
-    pub fn new(element: HtmlCanvasElement) -> Self {
+    pub fn new(
+        element: HtmlCanvasElement,
+        source: Rc<dyn FlightDataSource>,
+    ) -> Self {
         let ctx = get_ctx(&element).unwrap();
 
         let window = Rc::new(RefCell::new(Window::new()));
         let canvas = Rc::new(RefCell::new(Canvas::new(element, ctx)));
         let bounds = Rc::new(RefCell::new(LatLngBounds::default()));
-        let manager = Rc::new(RefCell::new(Manager::new()));
+        let clock = Rc::new(RefCell::new(Clock::real()));
+        let manager = Rc::new(RefCell::new(Manager::new(source)));
 
         Proxy {
             window,
             canvas,
             bounds,
+            clock,
             manager,
         }
     }
@@ -89,7 +104,7 @@ impl Proxy {
     pub async fn prepare(
         this: Arc<Proxy>,
         airports: JsValue,
-    ) -> Result<JsValue, JsValue> {
+    ) -> Result<JsValue, FlightPackError> {
         console::log_1(&(
             "[proxy] ++++ prepare()".into()
         ));
@@ -99,11 +114,14 @@ impl Proxy {
         // from FlightAware API, and extract
         // geo-coordinates from the fetched data
         // to give it back to JS.
+        let now = this.clock.borrow().now();
+
         this.manager
             .borrow_mut()
             .prepare(
                 &this.canvas.borrow().ctx,
                 airports,
+                now,
             ).await
     }
 
@@ -140,6 +158,7 @@ impl Proxy {
                     this.manager.borrow_mut().update(
                         this.canvas.clone(),
                         this.bounds.clone(),
+                        this.clock.clone(),
                     )
                 ) as Pin<Box<dyn Future<Output = ()>>>,
             ]).await;
@@ -167,4 +186,206 @@ impl Proxy {
             bounds.west,
         );
     }
+
+    /// What the map currently shows.
+    pub fn bounds(this: Arc<Proxy>) -> LatLngBounds {
+        this.bounds.borrow().clone()
+    }
+
+    /// Playback speed for `Clock`.
+    /// (e.g. `60.0` makes 1 second into 1 minute)
+    pub fn set_time_scale(this: Arc<Proxy>, scale: f64) -> Result<(), JsValue> {
+        this.clock
+            .borrow_mut()
+            .set_time_scale(scale)
+            .map_err(JsValue::from)
+    }
+
+    pub fn pause(this: Arc<Proxy>) {
+        this.clock.borrow_mut().pause();
+    }
+
+    pub fn resume(this: Arc<Proxy>) {
+        this.clock.borrow_mut().resume();
+    }
+
+    /// Jump `Clock` to `at` (in RFC 3339).
+    pub fn seek(this: Arc<Proxy>, at: String) -> Result<(), JsValue> {
+        let at: DateTime<Utc> = DateTime::parse_from_rfc3339(&at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|err| JsValue::from(
+                format!("Invalid timestamp \"{}\": {}", at, err)
+            ))?;
+
+        this.clock.borrow_mut().seek(at);
+        Ok(())
+    }
+
+    /// How many pages (at least 1) to follow for each
+    /// airport on the next `prepare`.
+    pub fn set_max_pages(this: Arc<Proxy>, max_pages: usize) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_max_pages(max_pages);
+
+        Ok(())
+    }
+
+    /// Switch flight paths between `"great_circle"`
+    /// and `"rhumb"` for `flight_id` (or for all).
+    pub fn set_path_kind(
+        this: Arc<Proxy>,
+        kind: String,
+        flight_id: Option<String>,
+    ) -> Result<(), JsValue> {
+        let kind: FlightPathKind = FlightPathKind::from_name(&kind)
+            .ok_or_else(|| JsValue::from(
+                format!("Unknown flight path: {}", kind)
+            ))?;
+
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_path_kind(flight_id.as_deref(), kind);
+
+        Ok(())
+    }
+
+    /// Whatever is drawn at (`x`, `y`) given in CSS pixels
+    /// relative to the canvas (e.g. `offsetX` and `offsetY`
+    /// of mouse events). Since the canvas is sized
+    /// in device pixels, we scale them by `dpr`.
+    pub fn pick(
+        this: Arc<Proxy>,
+        x: f64,
+        y: f64,
+        tolerance: f64,
+    ) -> Result<Option<PickResult>, JsValue> {
+        let dpr: f64 = this.canvas.borrow().dpr;
+        let point = PointCoord::new(x * dpr, y * dpr);
+
+        let picked: Option<PickResult> = this.manager
+            .try_borrow()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .pick(&point, tolerance * dpr);
+
+        Ok(picked)
+    }
+
+    /// The geo-coordinate at (`x`, `y`) given in CSS pixels
+    /// (same as `pick`) using the projection in use.
+    pub fn unproject(this: Arc<Proxy>, x: f64, y: f64) -> Result<GeoCoord, JsValue> {
+        let dpr: f64 = this.canvas.borrow().dpr;
+        let point = PointCoord::new(x * dpr, y * dpr);
+
+        let coord: GeoCoord = this.manager
+            .try_borrow()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .projection()
+            .unproject(&point);
+
+        Ok(coord)
+    }
+
+    /// Switch projections between `"web_mercator"`
+    /// and `"equirectangular"`.
+    pub fn set_projection(this: Arc<Proxy>, name: String) -> Result<(), JsValue> {
+        let kind: ProjectionKind = ProjectionKind::from_name(&name)
+            .ok_or_else(|| JsValue::from(
+                format!("Unknown projection: {}", name)
+            ))?;
+
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_projection_kind(kind);
+
+        Ok(())
+    }
+
+    /// Selects `flight_id` to show its tooltip
+    /// (or unselect when `None`).
+    pub fn select(
+        this: Arc<Proxy>,
+        flight_id: Option<String>,
+    ) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .select(flight_id);
+
+        Ok(())
+    }
+
+    pub fn set_delay_thresholds(
+        this: Arc<Proxy>,
+        thresholds: JsValue,
+    ) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_delay_thresholds(thresholds)
+            .map_err(JsValue::from)
+    }
+
+    /// Color flight lines by `"delay"` or `"airline"`.
+    pub fn set_color_by(this: Arc<Proxy>, color_by: String) -> Result<(), JsValue> {
+        let color_by: FlightColorBy = FlightColorBy::from_name(&color_by)
+            .ok_or_else(|| JsValue::from(
+                format!("Unknown flight color: {}", color_by)
+            ))?;
+
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_color_by(color_by);
+
+        Ok(())
+    }
+
+    pub fn set_status_options(
+        this: Arc<Proxy>,
+        options: JsValue,
+    ) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_status_options(options)
+            .map_err(JsValue::from)
+    }
+
+    pub fn set_filter(
+        this: Arc<Proxy>,
+        filter: JsValue,
+    ) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .set_filter(filter)
+            .map_err(JsValue::from)
+    }
+
+    pub fn clear_filter(this: Arc<Proxy>) -> Result<(), JsValue> {
+        this.manager
+            .try_borrow_mut()
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))?
+            .clear_filter();
+
+        Ok(())
+    }
+
+    pub fn delay_legend(this: Arc<Proxy>) -> Result<Vec<DelayLegendEntry>, JsValue> {
+        this.manager
+            .try_borrow()
+            .map(|manager| manager.delay_legend())
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))
+    }
+
+    pub fn stats(this: Arc<Proxy>) -> Result<FlightStats, JsValue> {
+        this.manager
+            .try_borrow()
+            .map(|manager| manager.stats())
+            .map_err(|_| JsValue::from("Manager is busy".to_string()))
+    }
 }
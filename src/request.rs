@@ -11,23 +11,34 @@ use web_sys::{
 };
 
 use crate::constants::ALLOW_ORIGIN;
+use crate::error::FlightPackError;
 
+/// Sends a GET request to `url`, and resolves
+/// with the response body (which should be JSON).
 pub async fn fetch(
     url: &str,
     headers: Option<HashMap<String, String>>,
-) -> Result<JsValue, JsValue> {
+) -> Result<String, FlightPackError> {
+    let network_err = |err: JsValue| FlightPackError::Network {
+        url: url.into(),
+        message: err.as_string().unwrap_or_else(|| format!("{:?}", err)),
+    };
+
     let mut opts = RequestInit::new();
     opts.method("GET");
     opts.mode(RequestMode::Cors);
 
-    let request = Request::new_with_str_and_init(url, &opts)?;
+    let request = Request::new_with_str_and_init(url, &opts)
+        .map_err(network_err)?;
 
-    request.headers().set("Accept", "application/json")?;
+    request.headers().set("Accept", "application/json")
+        .map_err(network_err)?;
 
     if !ALLOW_ORIGIN.is_empty() {
         request
             .headers()
-            .set("Access-Control-Allow-Origin", ALLOW_ORIGIN)?;
+            .set("Access-Control-Allow-Origin", ALLOW_ORIGIN)
+            .map_err(network_err)?;
     }
 
     if let Some(headers) = headers {
@@ -40,9 +51,21 @@ pub async fn fetch(
     }
 
     let window = web_sys::window().unwrap();
-    let res_obj = JsFuture::from(window.fetch_with_request(&request)).await?;
+    let res_obj = JsFuture::from(window.fetch_with_request(&request))
+        .await
+        .map_err(network_err)?;
     let res: Response = res_obj.dyn_into().unwrap();
-    let json = JsFuture::from(res.json()?).await?;
 
-    Ok(json)
+    if !res.ok() {
+        return Err(FlightPackError::HttpStatus {
+            url: url.into(),
+            status: res.status(),
+        });
+    }
+
+    let text = JsFuture::from(res.text().map_err(network_err)?)
+        .await
+        .map_err(network_err)?;
+
+    Ok(text.as_string().unwrap_or_default())
 }
@@ -8,6 +8,7 @@ use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{
     CanvasRenderingContext2d,
     HtmlCanvasElement,
+    TextMetrics,
     Window,
 };
 
@@ -83,6 +84,24 @@ pub fn get_ctx(canvas: &HtmlCanvasElement) ->
     Ok(ctx)
 }
 
+/// Width and height of `text` when drawn in the current
+/// font of `ctx`. Falls back to `default` when failed.
+pub fn measure_text(
+    ctx: &CanvasRenderingContext2d,
+    text: &str,
+    default: (f64, f64),
+) -> (f64, f64) {
+    ctx.measure_text(text).map_or(
+        default,
+        |metrics: TextMetrics| {
+            let h = metrics.actual_bounding_box_ascent() +
+                metrics.actual_bounding_box_descent();
+            let w = metrics.width();
+            (w, h)
+        }
+    )
+}
+
 pub fn rad_to_deg(rad: f64) -> f64 { rad * (180.0 / PI) }
 pub fn deg_to_rad(deg: f64) -> f64 { deg * (PI / 180.0) }
 